@@ -0,0 +1,34 @@
+"""Pack snippets.toml into assets/dumps/snippets.dump for --offline.
+
+The dump uses the same format as syntect's compressed dumps: the snippets are
+serialized with bincode (as a list of language and code pairs) and compressed
+with zlib.
+"""
+
+import struct
+import tomllib
+import zlib
+from pathlib import Path
+
+ROOT = Path(__file__).resolve().parent
+
+
+def encode_string(value: str) -> bytes:
+    data = value.encode("utf-8")
+    return struct.pack("<Q", len(data)) + data
+
+
+def main() -> None:
+    with (ROOT / "snippets.toml").open("rb") as file:
+        snippets = tomllib.load(file)["snippet"]
+
+    data = struct.pack("<Q", len(snippets))
+    for snippet in snippets:
+        data += encode_string(snippet["language"]) + encode_string(snippet["code"])
+
+    (ROOT.parent / "dumps" / "snippets.dump").write_bytes(zlib.compress(data, 9))
+    print(f"Packed {len(snippets)} snippets")
+
+
+if __name__ == "__main__":
+    main()
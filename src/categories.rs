@@ -0,0 +1,185 @@
+use rand::{rngs::StdRng, seq::SliceRandom};
+
+/// A kind of program that code can be classified as with `--mode category`.
+pub struct Category {
+    pub name: &'static str,
+    /// Pieces of code that are typical of this kind of program.
+    pub markers: &'static [&'static str],
+}
+
+/// All categories, from the most specific to the least specific, since e.g. a
+/// test of a web server should be classified as a test.
+pub const CATEGORIES: [Category; 5] = [
+    Category {
+        name: "Test",
+        markers: &[
+            "#[test]",
+            "@Test",
+            "describe(",
+            "it(\"",
+            "it('",
+            "def test_",
+            "func Test",
+            "assert_eq!",
+            "assertEquals",
+            "expect(",
+            "unittest",
+            "pytest",
+        ],
+    },
+    Category {
+        name: "Web server",
+        markers: &[
+            "app.get(",
+            "app.post(",
+            "app.listen(",
+            "@app.route",
+            "express()",
+            "http.ListenAndServe",
+            "http.HandleFunc",
+            "HttpServer",
+            "@GetMapping",
+            "@RestController",
+            "(req, res)",
+        ],
+    },
+    Category {
+        name: "CLI tool",
+        markers: &[
+            "argparse",
+            "sys.argv",
+            "process.argv",
+            "os.Args",
+            "flag.Parse",
+            "env::args",
+            "getopt",
+            "clap::",
+            "String[] args",
+            "argc",
+        ],
+    },
+    Category {
+        name: "Config",
+        markers: &[
+            "module.exports = {",
+            "export default {",
+            "[dependencies]",
+            "<configuration",
+            "FROM ",
+            "ENV ",
+            "\"devDependencies\"",
+            "defineConfig(",
+        ],
+    },
+    Category {
+        name: "Algorithm",
+        markers: &[
+            "fibonacci",
+            "factorial",
+            "binary_search",
+            "binarySearch",
+            "quicksort",
+            "merge_sort",
+            "mergeSort",
+            "dp[",
+            "mid = ",
+            "swap(",
+            ".left",
+            "visited",
+        ],
+    },
+];
+
+/// Classify code by the first category it has a marker of. Like
+/// [`crate::dialects::detect`], this only looks at the code the player will
+/// actually see.
+#[must_use]
+pub fn classify(code: &str) -> Option<&'static Category> {
+    CATEGORIES
+        .iter()
+        .find(|category| category.markers.iter().any(|marker| code.contains(marker)))
+}
+
+/// Get the options for a round in `--mode category`, which are the correct
+/// category and 3 random others.
+#[must_use]
+pub fn get_options(correct: &Category, rng: &mut StdRng) -> Vec<&'static str> {
+    let mut options: Vec<_> = CATEGORIES
+        .iter()
+        .map(|category| category.name)
+        .filter(|name| *name != correct.name)
+        .collect();
+
+    options.shuffle(rng);
+    options.truncate(3);
+    options.push(correct.name);
+    options.shuffle(rng);
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use rand::SeedableRng;
+
+    fn classified(code: &str) -> Option<&'static str> {
+        classify(code).map(|category| category.name)
+    }
+
+    #[test]
+    fn clear_examples() {
+        let test = indoc! {"
+            #[test]
+            fn addition() {
+                assert_eq!(1 + 1, 2);
+            }
+        "};
+        assert_eq!(classified(test), Some("Test"));
+
+        let server = indoc! {"
+            const app = express();
+            app.get('/', (req, res) => res.send('Hello'));
+            app.listen(3000);
+        "};
+        assert_eq!(classified(server), Some("Web server"));
+
+        let cli = indoc! {"
+            import sys
+
+            if len(sys.argv) < 2:
+                sys.exit(1)
+        "};
+        assert_eq!(classified(cli), Some("CLI tool"));
+
+        let config = indoc! {"
+            module.exports = {
+                mode: 'production',
+            };
+        "};
+        assert_eq!(classified(config), Some("Config"));
+
+        let algorithm = indoc! {"
+            def fibonacci(n):
+                return n if n < 2 else fibonacci(n - 1) + fibonacci(n - 2)
+        "};
+        assert_eq!(classified(algorithm), Some("Algorithm"));
+
+        assert_eq!(classified("x = 5\n"), None);
+    }
+
+    #[test]
+    fn category_options() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for category in &CATEGORIES {
+            let mut options = get_options(category, &mut rng);
+
+            assert_eq!(options.len(), 4);
+            assert!(options.contains(&category.name));
+
+            options.sort_unstable();
+            options.dedup();
+            assert_eq!(options.len(), 4);
+        }
+    }
+}
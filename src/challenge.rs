@@ -0,0 +1,240 @@
+use crate::{
+    error::GuessError,
+    game::{Difficulty, Game, LANGUAGES},
+    providers::PROVIDERS,
+    random, Args, Result, CONFIG,
+};
+
+/// The characters used to encode a challenge code.
+pub const ALPHABET: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// The version of the challenge code format. This is stored in the code so
+/// that codes from incompatible versions can be rejected instead of silently
+/// producing a different game.
+pub const VERSION: u128 = 2;
+
+/// The number of characters the seed takes up at the start of a code, which
+/// is enough for any [`u64`].
+pub const SEED_WIDTH: usize = 11;
+
+/// The difficulties that can be stored in a challenge code, in the order of
+/// their encoded index.
+pub const DIFFICULTIES: [Difficulty; 4] = [
+    Difficulty::Easy,
+    Difficulty::Normal,
+    Difficulty::Hard,
+    Difficulty::Expert,
+];
+
+/// The settings that determine how a game plays out, which can be shared
+/// between players as a short base62 string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Challenge {
+    pub provider: String,
+    pub wait: u64,
+    pub shuffle: bool,
+    /// The seed that decides which code is played and its options.
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    /// The languages that can be played, or none if every one can be.
+    pub languages: Vec<&'static str>,
+}
+
+impl Challenge {
+    /// Get the challenge for a game played with `args`. There is none if the
+    /// game can't be shared, e.g. because it mixes providers or plays a URL or
+    /// repository.
+    #[must_use]
+    pub fn from_args(args: &Args) -> Option<Self> {
+        if args.url.is_some() || args.git.is_some() || args.offline {
+            return None;
+        }
+
+        let provider = args.provider().unwrap_or(PROVIDERS[0].0);
+        PROVIDERS.iter().find(|(name, _)| *name == provider)?;
+
+        let languages = args
+            .languages
+            .clone()
+            .or_else(|| (!CONFIG.languages.is_empty()).then(|| CONFIG.languages.join(",")))
+            .map_or_else(|| Ok(Vec::new()), |list| Game::parse_languages(&list))
+            .ok()?;
+
+        Some(Self {
+            provider: provider.to_string(),
+            wait: args.wait(),
+            shuffle: args.shuffle,
+            seed: args.seed.unwrap_or_else(random::seed),
+            difficulty: args.difficulty.or(CONFIG.difficulty).unwrap_or_default(),
+            languages: if languages.len() == LANGUAGES.len() {
+                Vec::new()
+            } else {
+                languages
+            },
+        })
+    }
+
+    /// Pack the settings into a single integer and encode it in base62,
+    /// after the seed. The layout (from the least significant bit) is:
+    /// - 4 bits: format version
+    /// - 4 bits: provider index
+    /// - 1 bit: shuffle
+    /// - 32 bits: wait in ms (saturating)
+    /// - 2 bits: difficulty index
+    /// - 1 bit for each of [`LANGUAGES`], set if it can be played
+    #[must_use]
+    pub fn encode(&self) -> String {
+        let provider = PROVIDERS
+            .iter()
+            .position(|(provider, _)| *provider == self.provider)
+            .unwrap_or_default() as u128;
+
+        let wait = u128::from(u32::try_from(self.wait).unwrap_or(u32::MAX));
+
+        let difficulty = DIFFICULTIES
+            .iter()
+            .position(|&difficulty| difficulty == self.difficulty)
+            .unwrap_or_default() as u128;
+
+        let languages = LANGUAGES
+            .iter()
+            .enumerate()
+            .filter(|(_, language)| self.languages.contains(language))
+            .fold(0, |mask, (idx, _)| mask | 1 << idx);
+
+        let packed = VERSION
+            | provider << 4
+            | u128::from(self.shuffle) << 8
+            | wait << 9
+            | difficulty << 41
+            | languages << 43;
+
+        let seed = to_base62(u128::from(self.seed));
+        format!("{seed:0>SEED_WIDTH$}{}", to_base62(packed))
+    }
+
+    /// Decode a challenge code created by [`Challenge::encode`].
+    pub fn decode(code: &str) -> Result<Self> {
+        let code = code.trim();
+        if code.len() <= SEED_WIDTH || !code.is_ascii() {
+            return Err(invalid("too short"));
+        }
+
+        let (seed, settings) = code.split_at(SEED_WIDTH);
+        let seed = u64::try_from(from_base62(seed)?).map_err(|_| invalid("seed is too big"))?;
+        let packed = from_base62(settings)?;
+
+        if packed & 0xF != VERSION {
+            return Err(invalid("unsupported version"));
+        }
+
+        let (provider, _) = PROVIDERS
+            .get((packed >> 4 & 0xF) as usize)
+            .ok_or_else(|| invalid("unknown provider"))?;
+
+        if packed >> (43 + LANGUAGES.len()) != 0 {
+            return Err(invalid("unexpected trailing data"));
+        }
+
+        let languages = LANGUAGES
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| packed >> (43 + idx) & 1 == 1)
+            .map(|(_, language)| *language)
+            .collect();
+
+        Ok(Self {
+            provider: (*provider).to_string(),
+            shuffle: packed >> 8 & 1 == 1,
+            wait: (packed >> 9 & 0xFFFF_FFFF) as u64,
+            seed,
+            difficulty: DIFFICULTIES[(packed >> 41 & 0b11) as usize],
+            languages,
+        })
+    }
+
+    /// Overwrite the relevant settings in `args` with those from the challenge.
+    pub fn apply(self, args: &mut Args) {
+        args.provider = Some(self.provider);
+        args.wait = Some(self.wait);
+        args.shuffle = self.shuffle;
+        args.seed = Some(self.seed);
+        args.difficulty = Some(self.difficulty);
+        args.languages = Some(if self.languages.is_empty() {
+            LANGUAGES.join(",")
+        } else {
+            self.languages.join(",")
+        });
+    }
+}
+
+/// Encode `value` in base62, with no leading zeros.
+fn to_base62(mut value: u128) -> String {
+    let mut code = Vec::new();
+    while value > 0 {
+        code.push(ALPHABET[(value % 62) as usize]);
+        value /= 62;
+    }
+
+    code.reverse();
+    String::from_utf8(code).unwrap()
+}
+
+/// Decode a base62 string created by [`to_base62`].
+fn from_base62(code: &str) -> Result<u128> {
+    let mut value: u128 = 0;
+
+    for char in code.bytes() {
+        let digit = ALPHABET
+            .iter()
+            .position(|&valid| valid == char)
+            .ok_or_else(|| invalid(&format!("unexpected character '{}'", char as char)))?;
+
+        value = value
+            .checked_mul(62)
+            .and_then(|value| value.checked_add(digit as u128))
+            .ok_or_else(|| invalid("too long"))?;
+    }
+
+    Ok(value)
+}
+
+/// Create the error for a malformed challenge code.
+fn invalid(reason: &str) -> GuessError {
+    GuessError::Config(format!("Invalid challenge code: {reason}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let challenge = Challenge {
+            provider: String::from("sourcegraph"),
+            wait: 5000,
+            shuffle: true,
+            seed: u64::MAX,
+            difficulty: Difficulty::Hard,
+            languages: vec!["Go", "Rust"],
+        };
+
+        assert_eq!(Challenge::decode(&challenge.encode()).unwrap(), challenge);
+
+        let challenge = Challenge {
+            seed: 0,
+            languages: Vec::new(),
+            ..challenge
+        };
+        assert_eq!(Challenge::decode(&challenge.encode()).unwrap(), challenge);
+    }
+
+    #[test]
+    fn malformed_codes() {
+        assert!(Challenge::decode("").is_err());
+        assert!(Challenge::decode("not-base62!").is_err());
+        assert!(Challenge::decode(&"z".repeat(30)).is_err());
+        assert!(Challenge::decode(&"z".repeat(12)).is_err());
+        assert!(Challenge::decode(&format!("{}1", "0".repeat(SEED_WIDTH))).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use rand::{rngs::StdRng, seq::SliceRandom};
+
+/// A dialect of one of [`crate::game::LANGUAGES`] that can be told apart from
+/// the language's other dialects, used with `--dialects`.
+pub struct Dialect {
+    pub name: &'static str,
+    pub language: &'static str,
+    /// Pieces of code that are only used in this dialect (or in newer ones).
+    pub markers: &'static [&'static str],
+}
+
+/// All dialects that can be detected. The dialects of a language are listed
+/// from newest to oldest, because newer dialects usually still accept the
+/// syntax of older ones.
+pub const DIALECTS: [Dialect; 4] = [
+    Dialect {
+        name: "Python 3",
+        language: "Python",
+        markers: &[
+            "f\"",
+            "f'",
+            "nonlocal ",
+            "async def ",
+            "await ",
+            "yield from ",
+            ") -> ",
+        ],
+    },
+    Dialect {
+        name: "Python 2",
+        language: "Python",
+        markers: &[
+            "print \"",
+            "print '",
+            "xrange(",
+            "raw_input(",
+            ".iteritems()",
+            ".has_key(",
+            "except Exception, ",
+        ],
+    },
+    Dialect {
+        name: "ES6+",
+        language: "JavaScript",
+        markers: &[
+            "=>", "let ", "const ", "class ", "`", "import ", "export ", "async ",
+        ],
+    },
+    Dialect {
+        name: "ES5",
+        language: "JavaScript",
+        markers: &["var ", "function"],
+    },
+];
+
+/// Detect the dialect of code written in `language`. This only looks at the
+/// code the player will actually see, since the round would otherwise be
+/// impossible to get right.
+#[must_use]
+pub fn detect(language: &str, code: &str) -> Option<&'static Dialect> {
+    DIALECTS
+        .iter()
+        .filter(|dialect| dialect.language == language)
+        .find(|dialect| dialect.markers.iter().any(|marker| code.contains(marker)))
+}
+
+/// Get the base language of an answer, which is either a language or the name
+/// of a dialect.
+#[must_use]
+pub fn base_language(answer: &str) -> &str {
+    DIALECTS
+        .iter()
+        .find(|dialect| dialect.name == answer)
+        .map_or(answer, |dialect| dialect.language)
+}
+
+/// Get the options for a round with a detected dialect. The other dialects of
+/// the same language are always included, since telling those apart is the
+/// point, and the remaining options are filled with random dialects.
+#[must_use]
+pub fn get_options(correct: &Dialect, rng: &mut StdRng) -> Vec<&'static str> {
+    let (mut siblings, mut others): (Vec<_>, Vec<_>) = DIALECTS
+        .iter()
+        .filter(|dialect| dialect.name != correct.name)
+        .partition(|dialect| dialect.language == correct.language);
+
+    siblings.shuffle(rng);
+    others.shuffle(rng);
+
+    let mut options: Vec<_> = siblings
+        .into_iter()
+        .chain(others)
+        .take(3)
+        .map(|dialect| dialect.name)
+        .collect();
+
+    options.push(correct.name);
+    options.shuffle(rng);
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use rand::SeedableRng;
+
+    fn detected(language: &str, code: &str) -> Option<&'static str> {
+        detect(language, code).map(|dialect| dialect.name)
+    }
+
+    #[test]
+    fn python_dialects() {
+        let python2 = indoc! {r#"
+            for i in xrange(10):
+                print "Hello %s" % i
+        "#};
+        assert_eq!(detected("Python", python2), Some("Python 2"));
+
+        let python3 = indoc! {r#"
+            def greet(name: str) -> None:
+                print(f"Hello {name}")
+        "#};
+        assert_eq!(detected("Python", python3), Some("Python 3"));
+
+        assert_eq!(detected("Python", "x = [1, 2, 3]\n"), None);
+    }
+
+    #[test]
+    fn javascript_dialects() {
+        let es5 = indoc! {"
+            var add = function (a, b) {
+                return a + b;
+            };
+        "};
+        assert_eq!(detected("JavaScript", es5), Some("ES5"));
+
+        let es6 = indoc! {"
+            var total = 0;
+            const add = (a, b) => a + b;
+        "};
+        assert_eq!(detected("JavaScript", es6), Some("ES6+"));
+
+        assert_eq!(detected("Rust", es6), None);
+    }
+
+    #[test]
+    fn dialect_options() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for dialect in &DIALECTS {
+            let options = get_options(dialect, &mut rng);
+
+            assert_eq!(options.len(), 4);
+            assert!(options.contains(&dialect.name));
+            assert!(DIALECTS
+                .iter()
+                .filter(|other| other.language == dialect.language)
+                .all(|other| options.contains(&other.name)));
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::{error::Error, fmt, io, sync::PoisonError};
+
+/// Everything that can go wrong while playing, grouped by what the user can do
+/// about it.
+#[derive(Debug)]
+pub enum GuessError {
+    /// A request to Github failed.
+    Network(Box<ureq::Error>),
+    /// Github's ratelimit was reached, even after waiting for it to reset.
+    Ratelimited(String),
+    /// A token is missing, malformed, or rejected by Github.
+    Auth(String),
+    /// No usable code could be found.
+    NoSnippet(String),
+    /// The config file or arguments are invalid.
+    Config(String),
+    /// Reading from or writing to the terminal (or a response body) failed.
+    Io(io::Error),
+    /// Anything else, e.g. a poisoned lock.
+    Other(String),
+}
+
+impl GuessError {
+    /// The code the process should exit with when this error ends the game.
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Io(_) | Self::Other(_) => 1,
+            Self::Config(_) => 2,
+            Self::Network(_) | Self::Ratelimited(_) => 3,
+            Self::Auth(_) => 4,
+            Self::NoSnippet(_) => 5,
+        }
+    }
+}
+
+impl fmt::Display for GuessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(err) => write!(f, "Could not reach Github: {err}"),
+            Self::Ratelimited(message)
+            | Self::Auth(message)
+            | Self::NoSnippet(message)
+            | Self::Config(message)
+            | Self::Other(message) => write!(f, "{message}"),
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+        }
+    }
+}
+
+impl Error for GuessError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Network(err) => Some(err),
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ureq::Error> for GuessError {
+    fn from(err: ureq::Error) -> Self {
+        Self::Network(Box::new(err))
+    }
+}
+
+impl From<io::Error> for GuessError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<confy::ConfyError> for GuessError {
+    fn from(err: confy::ConfyError) -> Self {
+        Self::Config(err.to_string())
+    }
+}
+
+impl<T> From<PoisonError<T>> for GuessError {
+    fn from(_: PoisonError<T>) -> Self {
+        Self::Other(String::from("could not lock"))
+    }
+}
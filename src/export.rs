@@ -0,0 +1,72 @@
+use std::fs;
+
+use serde::Serialize;
+use ureq::serde_json;
+
+use crate::{error::GuessError, Result};
+
+/// A round that was answered, as written with `--export`.
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct RoundRecord {
+    /// The language of the code, or its dialect or category in those modes.
+    pub language: String,
+    /// The options, in the order they were shown.
+    pub options: Vec<String>,
+    /// The option that was chosen.
+    pub answer: String,
+    pub was_correct: bool,
+    /// The points won in the round, which are negative if a wager was lost.
+    pub points: i64,
+}
+
+/// The result of a game, written as JSON with `--export` when it ends.
+#[derive(Serialize, Debug)]
+pub struct Session<'a> {
+    pub score: u32,
+    /// The high score after this game, which may have been set by it.
+    pub high_score: u32,
+    pub rounds_played: usize,
+    /// The seed given with `--seed`, which can be used to play the same game
+    /// again.
+    pub seed: Option<u64>,
+    pub rounds: &'a [RoundRecord],
+}
+
+impl Session<'_> {
+    /// Write the session to `path` as pretty-printed JSON.
+    pub fn write(&self, path: &str) -> Result<()> {
+        let json =
+            serde_json::to_string_pretty(self).map_err(|err| GuessError::Other(err.to_string()))?;
+
+        fs::write(path, json)
+            .map_err(|err| GuessError::Config(format!("Couldn't export the game to {path}: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_json() {
+        let rounds = [RoundRecord {
+            language: String::from("Rust"),
+            options: vec![String::from("Go"), String::from("Rust")],
+            answer: String::from("Go"),
+            was_correct: false,
+            points: -25,
+        }];
+        let session = Session {
+            score: 0,
+            high_score: 300,
+            rounds_played: rounds.len(),
+            seed: Some(42),
+            rounds: &rounds,
+        };
+
+        let json = serde_json::to_value(&session).unwrap();
+        assert_eq!(json["seed"], 42);
+        assert_eq!(json["rounds"][0]["answer"], "Go");
+        assert_eq!(json["rounds"][0]["points"], -25);
+    }
+}
@@ -1,31 +1,52 @@
 use std::{
-    io::stdout,
+    collections::{HashMap, VecDeque},
+    fmt,
     ops::ControlFlow,
+    result,
+    str::FromStr,
     sync::{
-        mpsc::{self, Receiver},
-        Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+        Arc, Mutex,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crossterm::{
     cursor::{MoveTo, Show},
+    event::DisableMouseCapture,
     execute,
     style::Stylize,
     terminal::{disable_raw_mode, Clear, ClearType, LeaveAlternateScreen},
 };
-use rand::{seq::SliceRandom, thread_rng};
+use lazy_static::lazy_static;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    providers::{gists::GistProvider, repos::RepositoryProvider, GithubProvider},
-    terminal::Terminal,
+    categories, dialects,
+    error::GuessError,
+    export::{RoundRecord, Session},
+    hints::RoundHints,
+    providers::{
+        self,
+        cache::CachedProvider,
+        composite::CompositeProvider,
+        git::GitProvider,
+        offline::OfflineProvider,
+        preload::{Preloader, SharedProvider},
+        url::UrlProvider,
+        CodeData, GithubProvider, PROVIDERS,
+    },
+    random::{self, Rngs, SharedRng},
+    scoring,
+    strings::{fill, STRINGS},
+    terminal::{CodeLine, Layout, RevealMode, RevealSignal, Terminal, CLAMPED_LINES},
     Config, Result, ARGS, CONFIG,
 };
 
-/// The prompt to be shown before the options in [`Terminal::print_round_info`].
-pub const PROMPT: &str = "Which programming language is this? (Type the corresponding number)";
-
 /// All valid languages (top 24 from the Stack Overflow 2022 Developer survey,
 /// but substituting VBA for Dockerfile).
 pub const LANGUAGES: [&str; 25] = [
@@ -56,11 +77,514 @@ pub const LANGUAGES: [&str; 25] = [
     "TypeScript",
 ];
 
+/// The number of remaining Github requests below which the next round isn't
+/// fetched in the background anymore.
+pub const PRELOAD_RATELIMIT_THRESHOLD: u32 = 10;
+
+/// How many snippets are fetched for a round before giving up, when none of
+/// them have playable code.
+pub const MAX_FETCH_ATTEMPTS: u32 = 20;
+
+/// How many more times than the least common language a language can appear
+/// before it's left out until the others catch up, unless `--unbalanced` is
+/// provided.
+pub const BALANCE_SLACK: u32 = 1;
+
+/// How often a round is a bonus round with `--bonus-rounds`, e.g. every fifth
+/// round.
+pub const BONUS_ROUND_INTERVAL: u32 = 5;
+
+/// The percentages of their total points that players can cycle through
+/// wagering with `--wager`.
+pub const WAGER_PERCENTAGES: [u32; 4] = [0, 25, 50, 100];
+
+/// The most points `--speed-bonus` can award, regardless of its value.
+pub const MAX_SPEED_BONUS: u32 = 50;
+
+/// How long after a round is rendered the speed bonus lasts, decreasing
+/// linearly until then.
+pub const SPEED_BONUS_WINDOW: Duration = Duration::from_secs(10);
+
+/// How long a game with `--mode blitz` lasts, starting from when its first
+/// round is shown.
+pub const BLITZ_TIME: Duration = Duration::from_secs(120);
+
+/// How long the result of a wrong answer, or of a replayed round, is shown
+/// before moving on. This is separate from `--reveal-interval`, since it's
+/// about reading the result rather than the code.
+pub const RESULT_PAUSE: Duration = Duration::from_millis(1500);
+
+/// How long the result of a wrong answer is shown when a note is shown along
+/// with it, like with `--teach`.
+pub const NOTE_PAUSE: Duration = Duration::from_secs(5);
+
+/// How many rounds after being guessed wrong a language is played again with
+/// `--practice-wrong`.
+pub const PRACTICE_DELAY: u32 = 2;
+
+lazy_static! {
+    /// Why fetched files were skipped, which is shown when the game ends with
+    /// `--verbose`.
+    pub static ref SKIPPED_FILES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+}
+
+/// Notes on how pairs of languages are related, shown after a wrong answer
+/// when `--teach` is provided.
+pub const LANGUAGE_NOTES: [(&str, &str, &str); 18] = [
+    ("TypeScript", "JavaScript", "TypeScript is a superset of JavaScript that adds static types."),
+    ("C++", "C", "C++ started as \"C with Classes\" and is still mostly compatible with C."),
+    ("C#", "Java", "C# and Java are both garbage collected, class-based languages with C-like syntax."),
+    ("Kotlin", "Java", "Kotlin runs on the JVM and is fully interoperable with Java."),
+    ("Groovy", "Java", "Groovy runs on the JVM and most Java code is also valid Groovy."),
+    ("Kotlin", "Groovy", "Kotlin and Groovy are both JVM languages, and both are used for Gradle build scripts."),
+    ("Swift", "Kotlin", "Swift and Kotlin are modern mobile languages with very similar syntax."),
+    ("Dart", "JavaScript", "Dart was designed as an alternative to JavaScript and can compile to it."),
+    ("Dart", "Java", "Dart borrows its class syntax from Java and C#."),
+    ("Rust", "C++", "Rust and C++ are both systems languages without a garbage collector."),
+    ("Go", "C", "Go was co-designed by Ken Thompson, one of the creators of C."),
+    ("Shell", "PowerShell", "Shell (bash) and PowerShell are both command-line shells, but PowerShell pipes objects instead of text."),
+    ("Ruby", "Python", "Ruby and Python are both dynamic scripting languages, but Ruby uses `end` instead of indentation."),
+    ("R", "MATLAB", "R and MATLAB are both used for numerical and statistical computing."),
+    ("Lua", "Python", "Lua and Python are both dynamic, embeddable scripting languages."),
+    ("PHP", "HTML", "PHP is usually embedded in HTML to render web pages."),
+    ("Python 3", "Python 2", "Python 3 made print a function, added f-strings and type hints, and removed xrange."),
+    ("ES6+", "ES5", "ES6 added let/const, arrow functions, classes, template literals, and modules."),
+];
+
+/// The name the checkpoint of the current game is stored under, alongside the
+/// config.
+pub const CHECKPOINT_NAME: &str = "guess-that-lang-checkpoint";
+
+/// The state of a game, saved after every round so it can be continued with
+/// `--resume` if the game exits unexpectedly.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Checkpoint {
+    pub points: u32,
+    /// The number of rounds played. This is 0 when there's nothing to resume.
+    pub rounds: u32,
+    /// The seed the game was played with, which is used again when it's
+    /// resumed without `--seed` (see [`random::seed`]). It's stored as a
+    /// string because TOML integers can't be larger than `i64::MAX`, and
+    /// values have to come before tables like the streaks to be stored as
+    /// TOML.
+    #[serde(default)]
+    pub seed: Option<String>,
+    pub streaks: HashMap<String, u32>,
+    /// The answers given so far, which `--max-rounds` and bonus rounds are
+    /// counted from.
+    #[serde(default)]
+    pub accuracy: Accuracy,
+}
+
+impl Checkpoint {
+    /// Get the seed of the game being continued with `--resume`, if there is
+    /// one.
+    #[must_use]
+    pub fn resumed_seed() -> Option<u64> {
+        if !ARGS.resume {
+            return None;
+        }
+
+        confy::load::<Self>(CHECKPOINT_NAME)
+            .ok()?
+            .seed?
+            .parse()
+            .ok()
+    }
+}
+
+/// Pieces of code that are characteristic of each language, shown after a
+/// round when `--explain` is provided.
+pub const LANGUAGE_CUES: [(&str, &[&str]); 25] = [
+    (
+        "Assembly",
+        &["mov", "eax", "rax", "section", "syscall", "jmp"],
+    ),
+    ("Shell", &["fi", "esac", "$(", "echo", "then", "done"]),
+    (
+        "C",
+        &[
+            "#include",
+            "printf(",
+            "malloc(",
+            "->",
+            "typedef struct",
+            "NULL",
+        ],
+    ),
+    (
+        "C#",
+        &[
+            "using System",
+            "namespace",
+            "Console.WriteLine",
+            "async Task",
+            "get;",
+            "=>",
+        ],
+    ),
+    (
+        "C++",
+        &["std::", "#include", "template<", "cout", "nullptr", "auto"],
+    ),
+    (
+        "CSS",
+        &[
+            "px;",
+            "color:",
+            "margin",
+            "@media",
+            "!important",
+            "display:",
+        ],
+    ),
+    (
+        "Dart",
+        &[
+            "void main(",
+            "final",
+            "Widget",
+            "@override",
+            "setState",
+            "required",
+        ],
+    ),
+    (
+        "Dockerfile",
+        &["FROM", "RUN", "COPY", "CMD", "WORKDIR", "ENTRYPOINT"],
+    ),
+    (
+        "Go",
+        &["func", ":=", "package", "err != nil", "chan", "defer"],
+    ),
+    (
+        "Groovy",
+        &["def", "println", "->", "task", "apply plugin", "it."],
+    ),
+    (
+        "HTML",
+        &["<div", "</", "<!DOCTYPE", "class=\"", "<a href", "<script"],
+    ),
+    (
+        "Java",
+        &[
+            "public static void",
+            "System.out",
+            "import java.",
+            "@Override",
+            "extends",
+            "throws",
+        ],
+    ),
+    (
+        "JavaScript",
+        &[
+            "function",
+            "const",
+            "=>",
+            "document.",
+            "require(",
+            "console.log",
+        ],
+    ),
+    (
+        "Kotlin",
+        &["fun", "val", "?.", "data class", "when (", "println("],
+    ),
+    ("Lua", &["local", "end", "then", "~=", "..", "nil"]),
+    (
+        "MATLAB",
+        &["end", "function", "zeros(", "disp(", ".*", "fprintf("],
+    ),
+    (
+        "PHP",
+        &["<?php", "$this->", "echo", "=>", "namespace", "->"],
+    ),
+    (
+        "PowerShell",
+        &["$_", "Get-", "-eq", "param(", "Write-Host", "$true"],
+    ),
+    (
+        "Python",
+        &["def", "self", "elif", "None", "__init__", "import"],
+    ),
+    (
+        "R",
+        &["<-", "library(", "function(", "c(", "data.frame", "%>%"],
+    ),
+    (
+        "Ruby",
+        &["end", "def", "do |", "puts", "require", "attr_accessor"],
+    ),
+    ("Rust", &["fn", "let mut", "::", "impl", "&self", "match"]),
+    (
+        "SQL",
+        &[
+            "SELECT",
+            "FROM",
+            "WHERE",
+            "INSERT INTO",
+            "JOIN",
+            "CREATE TABLE",
+        ],
+    ),
+    (
+        "Swift",
+        &["func", "guard", "import UIKit", "let", "var", "struct"],
+    ),
+    (
+        "TypeScript",
+        &["interface", ": string", ": number", "=>", "export", "type"],
+    ),
+];
+
+/// What has to be guessed about the code.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    /// Guess the language.
+    Language,
+    /// Guess what kind of program it is, from [`categories::CATEGORIES`].
+    Category,
+    /// Guess the language in as many rounds as possible within
+    /// [`BLITZ_TIME`]. Wrong answers don't end the game, only the clock does.
+    Blitz,
+}
+
+impl FromStr for GameMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> result::Result<Self, Self::Err> {
+        match mode {
+            "language" => Ok(Self::Language),
+            "category" => Ok(Self::Category),
+            "blitz" => Ok(Self::Blitz),
+            _ => Err(format!(
+                "Invalid mode \"{mode}\" (expected language, category, or blitz)"
+            )),
+        }
+    }
+}
+
+/// How hard the game is, which changes how many options there are and how
+/// fast code is revealed and points decay.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    /// The answer is typed instead of chosen from options.
+    Expert,
+}
+
+impl Difficulty {
+    /// The difficulty from `--difficulty`, falling back to the one used last.
+    #[must_use]
+    pub fn current() -> Self {
+        ARGS.difficulty.or(CONFIG.difficulty).unwrap_or_default()
+    }
+
+    /// The number of options to choose from. With expert, there are no
+    /// options, but the typed answer and the correct one take up 2 rows.
+    #[must_use]
+    pub fn option_count(self) -> usize {
+        match self {
+            Self::Easy => 3,
+            Self::Normal => 4,
+            Self::Hard => 6,
+            Self::Expert => 2,
+        }
+    }
+
+    /// The number of ms to wait between revealing each step of the code after
+    /// the first, when `--reveal-interval` isn't given.
+    #[must_use]
+    pub fn reveal_interval(self) -> u64 {
+        match self {
+            Self::Easy => 2500,
+            Self::Normal | Self::Expert => 1500,
+            Self::Hard => 1000,
+        }
+    }
+
+    /// The points available before any code is revealed.
+    #[must_use]
+    pub fn starting_points(self) -> f32 {
+        match self {
+            Self::Easy | Self::Normal | Self::Expert => 100.0,
+            Self::Hard => 90.0,
+        }
+    }
+
+    /// The points lost for each line of code that's revealed.
+    #[must_use]
+    pub fn points_per_step(self) -> f32 {
+        match self {
+            Self::Easy | Self::Normal | Self::Expert => 10.0,
+            Self::Hard => 15.0,
+        }
+    }
+
+    /// Whether the answer is typed instead of chosen from options.
+    #[must_use]
+    pub fn is_typed(self) -> bool {
+        self == Self::Expert
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(difficulty: &str) -> result::Result<Self, Self::Err> {
+        match difficulty {
+            "easy" => Ok(Self::Easy),
+            "normal" => Ok(Self::Normal),
+            "hard" => Ok(Self::Hard),
+            "expert" => Ok(Self::Expert),
+            _ => Err(format!(
+                "Invalid difficulty \"{difficulty}\" (expected easy, normal, hard, or expert)"
+            )),
+        }
+    }
+}
+
+/// Points that are won or lost on top of the available points.
+#[derive(Clone, Copy, Default)]
+pub struct Stakes {
+    /// The points wagered with `--wager`, which are lost on a wrong answer.
+    pub wager: u32,
+    /// The points awarded for answering quickly with `--speed-bonus`.
+    pub speed_bonus: u32,
+    /// The number of answers in a row that were correct before this one,
+    /// which multiplies the available points with `--streak-bonus`.
+    pub streak: u32,
+    /// Whether the round is a bonus round, which doubles the available
+    /// points.
+    pub is_bonus: bool,
+}
+
+/// How many of this session's rounds were answered correctly, shown with
+/// `--accuracy`.
+#[derive(Clone, Copy, Default, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct Accuracy {
+    pub correct: u32,
+    pub answered: u32,
+    /// The number of answers in a row that were correct.
+    pub streak: u32,
+}
+
+impl Stakes {
+    /// The multiplier for the available points, from the streak and bonus
+    /// rounds.
+    #[must_use]
+    pub fn multiplier(&self) -> f32 {
+        let bonus = if self.is_bonus { 2.0 } else { 1.0 };
+        scoring::streak_multiplier(self.streak) * bonus
+    }
+
+    /// The points won in a round with the available points at
+    /// `available_points`, which are negative if a wager was lost.
+    #[must_use]
+    pub fn points_won(&self, was_correct: bool, available_points: f32) -> i64 {
+        if was_correct {
+            let awarded = scoring::apply_multiplier(available_points, self.multiplier());
+            i64::from(awarded + self.speed_bonus + self.wager)
+        } else {
+            -i64::from(self.wager)
+        }
+    }
+}
+
+impl Accuracy {
+    pub fn record(&mut self, is_correct: bool) {
+        self.answered += 1;
+        if is_correct {
+            self.correct += 1;
+            self.streak += 1;
+        } else {
+            self.streak = 0;
+        }
+    }
+}
+
+impl fmt::Display for Accuracy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.answered == 0 {
+            return write!(f, "-");
+        }
+
+        // Rounded to the nearest percent.
+        let percentage = (self.correct * 200 + self.answered) / (self.answered * 2);
+        write!(f, "{percentage}% ({}/{})", self.correct, self.answered)
+    }
+}
+
+/// What has to be guessed in a round.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RoundKind {
+    /// The language of the code (or its dialect or category).
+    Guess,
+    /// Whether two snippets, shown one after the other, are written in the
+    /// same language, for double points with `--bonus-rounds`.
+    SameLanguage,
+}
+
+/// The code for a round, which is fetched and parsed ahead of time so it can be
+/// preloaded while the previous round's result is shown.
+pub struct Round {
+    pub kind: RoundKind,
+    /// The correct option, which is the language for [`RoundKind::Guess`].
+    pub language: String,
+    pub code: Vec<CodeLine>,
+    pub options: Vec<String>,
+    /// All of the code, which is only parsed with `--review`.
+    pub full_code: Vec<CodeLine>,
+    /// Where the code is from (see [`CodeData::source`]).
+    pub source: Option<String>,
+    /// The wanted languages no code could be found in, which are counted as
+    /// if they appeared so that they don't hold back the others.
+    pub unavailable: Vec<&'static str>,
+}
+
 /// The all-encompassing game struct.
 pub struct Game {
     pub points: u32,
     pub terminal: Terminal,
-    pub provider: Box<dyn GithubProvider>,
+    pub provider: SharedProvider,
+    /// Fetches code in the background, unless every round is fetched up front
+    /// or a game has to be the same every time (see [`Game::new`]).
+    pub preloader: Option<Preloader>,
+    /// How many times in a row each language has been guessed correctly.
+    pub streaks: HashMap<String, u32>,
+    /// The round that was played last, kept so it can be replayed.
+    pub last_round: Option<Round>,
+    /// The number of rounds that have been answered correctly.
+    pub rounds: u32,
+    /// How many times each language has been used this session.
+    pub appearances: HashMap<String, u32>,
+    /// The answers given this session.
+    pub accuracy: Accuracy,
+    /// The rounds fetched up front with `--prefetch`, in the order they'll be
+    /// played.
+    pub prefetched: VecDeque<Round>,
+    /// The languages that were guessed wrong with `--practice-wrong`, in the
+    /// order they were missed, along with how many rounds are left until
+    /// they're played again.
+    pub missed: VecDeque<(&'static str, u32)>,
+    /// Whether every round of `--max-rounds` was played, rather than the game
+    /// ending early.
+    pub is_complete: bool,
+    /// The rounds answered this session, which are written with `--export`.
+    pub records: Vec<RoundRecord>,
+    /// When the game ends with `--mode blitz`, which is set once the first
+    /// round is shown.
+    pub deadline: Option<Instant>,
+    /// The number of wrong answers left before the game ends with `--lives`.
+    pub lives: Option<u32>,
+    /// The generators every random choice is made with, which are created
+    /// from the seed (see [`random::seed`]).
+    pub rngs: Rngs,
 }
 
 /// Cleanup terminal after the Game is over (this will also account for
@@ -68,27 +592,62 @@ pub struct Game {
 impl Drop for Game {
     fn drop(&mut self) {
         let _raw = disable_raw_mode();
-        let _leave = execute!(self.terminal.stdout, Show, LeaveAlternateScreen);
+        if let Ok(mut writer) = self.terminal.lock() {
+            let _leave = execute!(writer, Show, LeaveAlternateScreen);
 
-        println!(
-            "\nYou scored {} points!",
-            self.points.to_string().green().bold()
-        );
+            if ARGS.mouse {
+                let _mouse = execute!(writer, DisableMouseCapture);
+            }
+        }
 
-        if self.points > CONFIG.high_score {
-            if CONFIG.high_score > 0 {
+        let _summary = self
+            .terminal
+            .print_summary(self.points, CONFIG.high_score, &self.records);
+
+        if self.lives == Some(0) {
+            println!("{}", STRINGS.out_of_lives);
+        }
+
+        // Blitz mode always ends when the time is up, unless the user quit.
+        if ARGS.mode == GameMode::Blitz && self.is_time_up() {
+            println!("{}", STRINGS.time_up.as_str().yellow().bold());
+        }
+
+        // With `--max-rounds`, the game may have ended before every round was
+        // played.
+        if let Some(max_rounds) = ARGS.max_rounds {
+            if !self.is_complete {
                 println!(
-                    "You beat your high score of {}!\n\nShare it: {}",
-                    CONFIG.high_score.to_string().magenta().bold(),
-                    "https://github.com/Lioness100/guess-that-lang/discussions/6"
-                        .cyan()
-                        .bold()
+                    "{}",
+                    fill(
+                        &fill(&STRINGS.ended_early, self.accuracy.answered),
+                        max_rounds
+                    )
+                    .dark_grey()
                 );
             }
+        }
+
+        if let Ok(skipped) = SKIPPED_FILES.lock() {
+            for message in skipped.iter() {
+                println!("{}", message.as_str().dark_grey());
+            }
+        }
+
+        let clamped_lines = CLAMPED_LINES.load(Ordering::Relaxed);
+        if clamped_lines != usize::MAX {
+            println!(
+                "{}",
+                fill(&STRINGS.lines_clamped, clamped_lines).dark_grey()
+            );
+        }
 
+        if self.points > CONFIG.high_score {
+            // The config is reloaded because it may have changed during the
+            // game, e.g. when the stats were moved out of it.
             let new_config = Config {
                 high_score: self.points,
-                ..CONFIG.clone()
+                ..confy::load("guess-that-lang").unwrap_or_else(|_| CONFIG.clone())
             };
 
             let _config = confy::store("guess-that-lang", new_config);
@@ -97,130 +656,1670 @@ impl Drop for Game {
 }
 
 impl Game {
+    /// Check that the arguments can be played with together.
+    fn check_args() -> Result<()> {
+        if ARGS.prefetch && ARGS.max_rounds.is_none() {
+            return Err(GuessError::Config(String::from(
+                "--prefetch requires --max-rounds",
+            )));
+        }
+
+        // Shuffled lines can't be revealed from the bottom up.
+        if ARGS.shuffle && ARGS.reveal == RevealMode::BottomUp {
+            return Err(GuessError::Config(String::from(
+                "--shuffle can't be used with --reveal bottom-up",
+            )));
+        }
+
+        // Typed answers can't be wagered on or hinted at, since w and h are
+        // typed like any other letter.
+        if ARGS.wager && Difficulty::current().is_typed() {
+            return Err(GuessError::Config(String::from(
+                "--wager can't be used with --difficulty expert",
+            )));
+        }
+
+        if ARGS.hints && Difficulty::current().is_typed() {
+            return Err(GuessError::Config(String::from(
+                "--hints can't be used with --difficulty expert",
+            )));
+        }
+
+        // Bonus rounds have their own options, which can't be typed and aren't
+        // categories.
+        if ARGS.bonus_rounds && Difficulty::current().is_typed() {
+            return Err(GuessError::Config(String::from(
+                "--bonus-rounds can't be used with --difficulty expert",
+            )));
+        }
+
+        if ARGS.bonus_rounds && ARGS.mode == GameMode::Category {
+            return Err(GuessError::Config(String::from(
+                "--bonus-rounds can't be used with --mode category",
+            )));
+        }
+
+        if ARGS.lives == Some(0) {
+            return Err(GuessError::Config(String::from(
+                "--lives has to be at least 1",
+            )));
+        }
+
+        if ARGS.max_lines == 0 {
+            return Err(GuessError::Config(String::from(
+                "--max-lines has to be at least 1",
+            )));
+        }
+
+        if let Some(list) = Self::language_list() {
+            let count = Self::parse_languages(&list)?.len();
+            let option_count = Difficulty::current().option_count();
+            if count < option_count {
+                return Err(GuessError::Config(format!(
+                    "At least {option_count} languages are needed to fill the options, but only {count} were given (--languages or the config's languages)"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Create new game.
     pub fn new() -> Result<Self> {
-        let provider: Box<dyn GithubProvider> = match ARGS
-            .provider
-            .as_ref()
-            .unwrap_or(&String::from("repos"))
-            .as_str()
-        {
-            "gists" => Box::new(GistProvider::new()?),
-            "repos" => Box::new(RepositoryProvider::new()?),
-            _ => return Err("Invalid github provider (repos/gists)".into()),
+        let provider = Self::create_provider()?;
+
+        Self::check_args()?;
+
+        let checkpoint = if ARGS.resume {
+            let checkpoint: Checkpoint = confy::load(CHECKPOINT_NAME)?;
+            if checkpoint.rounds == 0 {
+                return Err(GuessError::Config(String::from(
+                    "There is no game to resume",
+                )));
+            }
+
+            checkpoint
+        } else {
+            Checkpoint::default()
         };
 
-        Ok(Self {
+        let terminal = Terminal::new()?;
+
+        // The difficulty and palette are remembered so they don't have to be
+        // given every time, but not the difficulty of someone else's
+        // challenge. The config is reloaded because the theme may have just
+        // been stored in it.
+        let difficulty = ARGS.difficulty.filter(|_| ARGS.challenge.is_none());
+        if difficulty.is_some() || ARGS.palette.is_some() {
+            let config: Config = confy::load("guess-that-lang")?;
+            confy::store(
+                "guess-that-lang",
+                Config {
+                    difficulty: difficulty.or(config.difficulty),
+                    palette: ARGS.palette.or(config.palette),
+                    ..config
+                },
+            )?;
+        }
+
+        // With `--seed`, the worker would make the order random choices are
+        // made in vary between games.
+        let provider = Arc::new(Mutex::new(provider));
+        let rngs = Rngs::new(random::seed());
+        let preloader = (ARGS.url.is_none() && !ARGS.prefetch && ARGS.seed.is_none()).then(|| {
+            Preloader::start(
+                Arc::clone(&provider),
+                Self::selected_languages(),
+                Arc::clone(&rngs.content),
+            )
+        });
+
+        let mut game = Self {
             points: 0,
-            terminal: Terminal::new()?,
+            terminal,
             provider,
+            preloader,
+            streaks: HashMap::new(),
+            last_round: None,
+            rounds: 0,
+            appearances: HashMap::new(),
+            accuracy: Accuracy::default(),
+            prefetched: VecDeque::new(),
+            missed: VecDeque::new(),
+            is_complete: false,
+            records: Vec::new(),
+            deadline: None,
+            lives: ARGS.lives,
+            rngs,
+        };
+        game.restore(checkpoint);
+
+        // A resumed game only fetches the rounds it has left.
+        if let (true, Some(max_rounds)) = (ARGS.prefetch, ARGS.max_rounds) {
+            game.prefetch(max_rounds.saturating_sub(game.accuracy.answered))?;
+        }
+
+        Ok(game)
+    }
+
+    /// Create the provider chosen with `--git`, `--url`, `--offline`, or
+    /// `--provider` (see [`Args::provider`]).
+    /// Code from the network is cached on disk unless `--no-cache` is
+    /// provided, and is played from the cache or the bundled snippets when it
+    /// can't be fetched (see [`CachedProvider`]).
+    pub fn create_provider() -> Result<Box<dyn GithubProvider>> {
+        if ARGS.git.is_some() {
+            if ARGS.offline || ARGS.url.is_some() || ARGS.provider.is_some() {
+                return Err(GuessError::Config(String::from(
+                    "--git can't be used with --provider, --url, or --offline",
+                )));
+            }
+
+            // The clone is already on disk, so it doesn't need to be cached.
+            return Ok(Box::new(GitProvider::new()?));
+        }
+
+        if ARGS.offline && (ARGS.url.is_some() || ARGS.provider.is_some()) {
+            return Err(GuessError::Config(String::from(
+                "--offline can't be used with --provider or --url",
+            )));
+        }
+
+        Ok(match (&ARGS.url, &ARGS.provider) {
+            (Some(_), Some(_)) => {
+                return Err(GuessError::Config(String::from(
+                    "--url can't be used with --provider",
+                )))
+            }
+            (Some(_), None) => Box::new(UrlProvider::new()?),
+            (None, None) if ARGS.offline => Box::new(OfflineProvider::new()?),
+            (None, _) => {
+                let spec = ARGS.provider().unwrap_or(PROVIDERS[0].0);
+                CompositeProvider::from_spec(spec, Self::create_named_provider)?
+            }
+        })
+    }
+
+    /// Create the provider registered as `name`, which is cached unless it
+    /// reads local files, since they're already on disk.
+    pub fn create_named_provider(name: &str) -> Result<Box<dyn GithubProvider>> {
+        let provider = providers::create(name)?;
+
+        Ok(if name == "local" {
+            provider
+        } else {
+            Box::new(CachedProvider::wrap(provider))
         })
     }
 
-    /// Get the language options for a round. This will choose 3 random unique
-    /// languages, push them to a vec along with the correct language, and
-    /// shuffle the vec.
+    /// Fetch every round of the game up front with `--prefetch`, so that no
+    /// requests have to be made while playing.
+    pub fn prefetch(&mut self, rounds: u32) -> Result<()> {
+        for fetched in 0..rounds {
+            self.terminal.print_prefetch_progress(fetched, rounds)?;
+
+            // The last round isn't known yet, so the last fetched one is
+            // avoided instead.
+            let pool = match self.prefetched.back() {
+                Some(previous) if !ARGS.allow_repeat_language => {
+                    Self::narrow_pool(self.language_pool(), |language| {
+                        language == dialects::base_language(&previous.language)
+                    })
+                }
+                _ => self.language_pool(),
+            };
+            let wanted = self.wanted_languages(&pool);
+            let round = Self::fetch_round(
+                &self.provider,
+                None,
+                &self.terminal,
+                &self.rngs.content,
+                &pool,
+                &wanted,
+                false,
+            )?;
+
+            self.prefetched.push_back(round);
+        }
+
+        let mut writer = self.terminal.lock()?;
+        execute!(writer, Clear(ClearType::All), MoveTo(0, 0)).map_err(Into::into)
+    }
+
+    /// Save the state of the game so it can be resumed. Failing to do so
+    /// shouldn't interrupt the game, so errors are ignored.
+    pub fn save_checkpoint(&self) {
+        let _checkpoint = confy::store(CHECKPOINT_NAME, self.checkpoint());
+    }
+
+    /// Get the state of the game to save with [`Game::save_checkpoint`].
+    #[must_use]
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            points: self.points,
+            rounds: self.rounds,
+            seed: Some(random::seed().to_string()),
+            streaks: self.streaks.clone(),
+            accuracy: self.accuracy,
+        }
+    }
+
+    /// Continue from the state saved in `checkpoint`.
+    pub fn restore(&mut self, checkpoint: Checkpoint) {
+        self.points = checkpoint.points;
+        self.rounds = checkpoint.rounds;
+        self.streaks = checkpoint.streaks;
+        self.accuracy = checkpoint.accuracy;
+    }
+
+    /// Write the result of the game with `--export`. This is done when the
+    /// game ends normally rather than when it's dropped, so that failing to
+    /// write it can be reported.
+    pub fn export(&self) -> Result<()> {
+        let path = match &ARGS.export {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        Session {
+            score: self.points,
+            high_score: self.points.max(CONFIG.high_score),
+            rounds_played: self.records.len(),
+            seed: ARGS.seed,
+            rounds: &self.records,
+        }
+        .write(path)
+    }
+
+    /// Forget the saved state once the game has ended normally.
+    pub fn clear_checkpoint() {
+        let _checkpoint = confy::store(CHECKPOINT_NAME, Checkpoint::default());
+    }
+
+    /// Parse a comma-separated list of languages, as given to `--languages`.
+    /// Names are matched case-insensitively, and unknown ones are reported
+    /// along with the closest valid name and every valid one.
+    pub fn parse_languages(list: &str) -> Result<Vec<&'static str>> {
+        let mut languages = Vec::new();
+
+        for name in list
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let language = LANGUAGES
+                .iter()
+                .find(|language| language.eq_ignore_ascii_case(name))
+                .ok_or_else(|| {
+                    let closest = LANGUAGES
+                        .iter()
+                        .min_by_key(|language| {
+                            edit_distance(&language.to_lowercase(), &name.to_lowercase())
+                        })
+                        .unwrap();
+
+                    GuessError::Config(format!(
+                        "Unknown language \"{name}\". Did you mean \"{closest}\"? Valid languages: {}",
+                        LANGUAGES.join(", ")
+                    ))
+                })?;
+
+            if !languages.contains(language) {
+                languages.push(*language);
+            }
+        }
+
+        Ok(languages)
+    }
+
+    /// Get the list of languages to play with from `--languages`, or the
+    /// config's `languages` if it isn't given.
+    #[must_use]
+    pub fn language_list() -> Option<String> {
+        ARGS.languages
+            .clone()
+            .or_else(|| (!CONFIG.languages.is_empty()).then(|| CONFIG.languages.join(",")))
+    }
+
+    /// Get the languages from [`Game::language_list`], or every language if
+    /// there isn't one. The list is validated when the game starts.
+    #[must_use]
+    pub fn selected_languages() -> Vec<&'static str> {
+        Self::language_list()
+            .and_then(|list| Self::parse_languages(&list).ok())
+            .filter(|languages| !languages.is_empty())
+            .unwrap_or_else(|| LANGUAGES.to_vec())
+    }
+
+    /// Get the languages that can be used in the next round. If `--master-after`
+    /// is provided, languages that have been guessed correctly that many times
+    /// in a row are excluded, and unless `--allow-repeat-language` is
+    /// provided, so is the language of the last round.
+    #[must_use]
+    pub fn language_pool(&self) -> Vec<&'static str> {
+        let pool = Self::narrow_pool(Self::selected_languages(), |language| {
+            ARGS.master_after.map_or(false, |master_after| {
+                self.streaks.get(language).copied().unwrap_or_default() >= master_after
+            })
+        });
+
+        match &self.last_round {
+            Some(round) if !ARGS.allow_repeat_language => Self::narrow_pool(pool, |language| {
+                language == dialects::base_language(&round.language)
+            }),
+            _ => pool,
+        }
+    }
+
+    /// Remove the languages matching `exclude` from `pool`, unless that would
+    /// leave it empty.
+    #[must_use]
+    pub fn narrow_pool(
+        pool: Vec<&'static str>,
+        exclude: impl Fn(&str) -> bool,
+    ) -> Vec<&'static str> {
+        let narrowed: Vec<_> = pool
+            .iter()
+            .copied()
+            .filter(|language| !exclude(language))
+            .collect();
+
+        if narrowed.is_empty() {
+            pool
+        } else {
+            narrowed
+        }
+    }
+
+    /// Get the language options for a round. This will choose `count - 1`
+    /// random unique other languages (preferring those in `pool`), push them
+    /// to a vec along with the correct language, and shuffle the vec.
     #[must_use]
-    pub fn get_options(correct_language: &str) -> Vec<&str> {
-        let mut options = Vec::<&str>::with_capacity(4);
+    pub fn get_options<'a>(
+        correct_language: &'a str,
+        pool: &[&'a str],
+        count: usize,
+        rng: &mut StdRng,
+    ) -> Vec<&'a str> {
+        let mut options = Vec::<&str>::with_capacity(count);
         options.push(correct_language);
 
-        let mut thread_rng = thread_rng();
-        while options.len() < 4 {
-            let random_language = LANGUAGES.choose(&mut thread_rng).unwrap();
-            if !options.contains(random_language) {
-                options.push(random_language);
+        let mut candidates = pool.to_vec();
+        candidates.shuffle(rng);
+
+        // Fall back to all selected languages if the pool is too small.
+        let mut fallback = Self::selected_languages();
+        fallback.shuffle(rng);
+
+        for language in candidates.into_iter().chain(fallback) {
+            if options.len() == count {
+                break;
+            }
+
+            if !options.contains(&language) {
+                options.push(language);
             }
         }
 
-        options.shuffle(&mut thread_rng);
+        options.shuffle(rng);
         options
     }
 
-    /// Start a new round, which is called in the main function with a for loop.
-    pub fn start_new_round(&mut self, preloader: Option<Receiver<()>>) -> Result<ControlFlow<()>> {
-        let data = self.provider.get_code()?;
-        let width = Terminal::width()?;
+    /// Get the speed bonus for answering `elapsed` after the round was
+    /// rendered, out of `max` (capped at [`MAX_SPEED_BONUS`]).
+    #[must_use]
+    pub fn get_speed_bonus(max: u32, elapsed: Duration) -> u32 {
+        let remaining = SPEED_BONUS_WINDOW.saturating_sub(elapsed);
+        let max = max.min(MAX_SPEED_BONUS);
+
+        (u128::from(max) * remaining.as_millis() / SPEED_BONUS_WINDOW.as_millis()) as u32
+    }
+
+    /// Get what's at stake in a round of `kind` answered now after it was
+    /// rendered at `rendered_at`, with the wager at `wager_level` and the
+    /// streak from `accuracy`.
+    #[must_use]
+    pub fn get_stakes(
+        total_points: u32,
+        wager_level: usize,
+        rendered_at: Instant,
+        accuracy: Accuracy,
+        kind: RoundKind,
+    ) -> Stakes {
+        Stakes {
+            wager: Self::get_wager(total_points, wager_level),
+            speed_bonus: ARGS
+                .speed_bonus
+                .map_or(0, |max| Self::get_speed_bonus(max, rendered_at.elapsed())),
+            streak: if ARGS.streak_bonus {
+                accuracy.streak
+            } else {
+                0
+            },
+            is_bonus: kind == RoundKind::SameLanguage,
+        }
+    }
+
+    /// Get the number of points wagered at a level of [`WAGER_PERCENTAGES`].
+    #[must_use]
+    pub fn get_wager(total_points: u32, level: usize) -> u32 {
+        total_points * WAGER_PERCENTAGES[level] / 100
+    }
+
+    /// Get the note relating two languages, if there is one.
+    #[must_use]
+    pub fn get_note(first: &str, second: &str) -> Option<&'static str> {
+        LANGUAGE_NOTES
+            .iter()
+            .find(|(a, b, _)| (*a, *b) == (first, second) || (*b, *a) == (first, second))
+            .map(|(_, _, note)| *note)
+    }
+
+    /// Get the cues from [`LANGUAGE_CUES`] that appear in the code. Cues that
+    /// start or end with a letter have to be whole words, so that e.g. `fn`
+    /// doesn't match `fnmatch`.
+    #[must_use]
+    pub fn get_cues(language: &str, code: &[CodeLine]) -> Vec<&'static str> {
+        let language = dialects::base_language(language);
+        let cues = match LANGUAGE_CUES.iter().find(|(name, _)| *name == language) {
+            Some((_, cues)) => cues,
+            None => return Vec::new(),
+        };
+
+        cues.iter()
+            .copied()
+            .filter(|cue| {
+                let is_word = |char: Option<char>| char.map_or(false, char::is_alphanumeric);
+                let pattern = format!(
+                    "{}{}{}",
+                    if is_word(cue.chars().next()) {
+                        r"\b"
+                    } else {
+                        ""
+                    },
+                    regex::escape(cue),
+                    if is_word(cue.chars().last()) {
+                        r"\b"
+                    } else {
+                        ""
+                    },
+                );
+
+                Regex::new(&pattern).map_or(false, |regex| {
+                    code.iter().any(|line| regex.is_match(&line.raw))
+                })
+            })
+            .collect()
+    }
+
+    /// Get the shown options besides the correct one that also have cues in
+    /// the code, which makes them reasonable guesses, along with those cues.
+    #[must_use]
+    pub fn get_feedback<'a>(
+        options: &[&'a str],
+        correct_language: &str,
+        code: &[CodeLine],
+    ) -> Vec<(&'a str, Vec<&'static str>)> {
+        options
+            .iter()
+            .filter(|option| **option != correct_language)
+            .map(|option| (*option, Self::get_cues(option, code)))
+            .filter(|(_, cues)| !cues.is_empty())
+            .collect()
+    }
+
+    /// Get the options that were shown in a round where option `num` was
+    /// chosen. With expert, the options that weren't typed were never shown.
+    #[must_use]
+    pub fn shown_options<'a>(options: &'a [&'a str], num: u32) -> &'a [&'a str] {
+        if Difficulty::current().is_typed() {
+            &options[num as usize - 1..num as usize]
+        } else {
+            options
+        }
+    }
+
+    /// Print the cues that gave away the correct language with `--explain`,
+    /// and after a wrong answer with `--feedback`, the other options that the
+    /// code had cues for.
+    pub fn explain_answer(
+        terminal: &Terminal,
+        language: &str,
+        options: &[&str],
+        code: &[CodeLine],
+        flow: ControlFlow<()>,
+    ) -> Result<()> {
+        if ARGS.explain {
+            terminal.print_cues(language, &Self::get_cues(language, code))?;
+        }
+
+        if ARGS.feedback && flow.is_break() {
+            terminal.print_feedback(&Self::get_feedback(options, language, code))?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the languages to ask the provider for. This is a single language
+    /// if one that was missed is due again (see [`Game::next_missed`]), or
+    /// otherwise the ones from [`Game::balanced_languages`], unless
+    /// `--unbalanced` is provided. The preloader is told to fetch them too.
+    pub fn wanted_languages(&mut self, pool: &[&'static str]) -> Vec<&'static str> {
+        if let Some(language) = Self::next_missed(&mut self.missed) {
+            return vec![language];
+        }
+
+        let wanted = if ARGS.unbalanced {
+            pool.to_vec()
+        } else {
+            Self::balanced_languages(pool, &self.appearances)
+        };
+
+        if let Some(preloader) = &self.preloader {
+            preloader.want(wanted.clone());
+        }
+
+        wanted
+    }
+
+    /// Queue a language that was guessed wrong to be played again after
+    /// [`PRACTICE_DELAY`] rounds.
+    pub fn queue_missed(missed: &mut VecDeque<(&'static str, u32)>, language: &str) {
+        let language = dialects::base_language(language);
+        if let Some(language) = LANGUAGES.iter().find(|&&other| other == language) {
+            missed.push_back((language, PRACTICE_DELAY));
+        }
+    }
+
+    /// Count down the rounds left for every missed language, and take the
+    /// first one that's due to be played again.
+    pub fn next_missed(missed: &mut VecDeque<(&'static str, u32)>) -> Option<&'static str> {
+        for (_, rounds_left) in missed.iter_mut() {
+            *rounds_left = rounds_left.saturating_sub(1);
+        }
+
+        // Languages are missed one round at a time, so the first one is always
+        // due first.
+        match missed.front() {
+            Some((_, 0)) => missed.pop_front().map(|(language, _)| language),
+            _ => None,
+        }
+    }
+
+    /// Get the languages in `pool` that haven't appeared more than
+    /// [`BALANCE_SLACK`] times more than the least common one, so that every
+    /// language is seen about as often over a session. There's more than one
+    /// unless every other language is ahead, so code can still be preloaded.
+    #[must_use]
+    pub fn balanced_languages(
+        pool: &[&'static str],
+        appearances: &HashMap<String, u32>,
+    ) -> Vec<&'static str> {
+        let count = |language: &str| appearances.get(language).copied().unwrap_or_default();
+        let least = pool
+            .iter()
+            .map(|language| count(language))
+            .min()
+            .unwrap_or_default();
+
+        pool.iter()
+            .copied()
+            .filter(|language| count(language) <= least + BALANCE_SLACK)
+            .collect()
+    }
+
+    /// Fetch and parse the code for a round, written in one of `wanted` if
+    /// possible, with the game's `rng`. The options are picked from `pool`. Code is taken from
+    /// `preloader` while it has any, and snippets that can't be played are
+    /// skipped, up to [`MAX_FETCH_ATTEMPTS`] times. If `is_bonus`, a bonus
+    /// round is fetched instead when possible. This only borrows the fields
+    /// it needs so that it can run in the background while the previous round
+    /// is still being shown.
+    pub fn fetch_round<'a>(
+        provider: &Mutex<Box<dyn GithubProvider>>,
+        mut preloader: Option<&mut Preloader>,
+        terminal: &Terminal,
+        rng: &SharedRng,
+        pool: &'a [&'static str],
+        mut wanted: &'a [&'static str],
+        is_bonus: bool,
+    ) -> Result<Round> {
+        if is_bonus {
+            if let Some(round) = Self::fetch_bonus_round(provider, terminal, rng, pool)? {
+                return Ok(round);
+            }
+        }
+
+        let mut unavailable = Vec::new();
+        for _ in 0..MAX_FETCH_ATTEMPTS {
+            // A single wanted language is one that's due (see
+            // [`Game::wanted_languages`]), which likely isn't preloaded.
+            let ready = match &mut preloader {
+                Some(preloader) if wanted.len() > 1 => preloader.take(wanted),
+                _ => None,
+            };
+
+            let data = match ready {
+                Some(data) => data?,
+                None => match provider.lock()?.get_code(wanted, &mut random::lock(rng)) {
+                    // Providers that can't search by language, like gists,
+                    // may not find any of the wanted languages, so any in the
+                    // pool is played instead and balanced out later.
+                    Err(GuessError::NoSnippet(_)) if wanted != pool => {
+                        unavailable = wanted.to_vec();
+                        wanted = pool;
+                        continue;
+                    }
+                    result => result?,
+                },
+            };
+            if let Some(mut round) =
+                Self::parse_round(terminal, &data, pool, &mut random::lock(rng))?
+            {
+                round.unavailable = unavailable;
+                return Ok(round);
+            }
+        }
+
+        Err(GuessError::NoSnippet(format!(
+            "Couldn't find playable code after {MAX_FETCH_ATTEMPTS} tries. Try again, or pass a token with --token so more code can be searched."
+        )))
+    }
+
+    /// Fetch two snippets from `provider` for a [`RoundKind::SameLanguage`]
+    /// round, which are written in the same language from `pool` half of the
+    /// time. This returns [`None`] if no playable pair is found, in which
+    /// case a normal round is played instead.
+    pub fn fetch_bonus_round(
+        provider: &Mutex<Box<dyn GithubProvider>>,
+        terminal: &Terminal,
+        rng: &SharedRng,
+        pool: &[&'static str],
+    ) -> Result<Option<Round>> {
+        for _ in 0..MAX_FETCH_ATTEMPTS {
+            let (first, second) = {
+                let mut rng = random::lock(rng);
+                let first = match pool.choose(&mut *rng) {
+                    Some(language) => *language,
+                    None => return Ok(None),
+                };
+                let others: Vec<_> = pool.iter().copied().filter(|&l| l != first).collect();
+
+                match others.choose(&mut *rng) {
+                    Some(&other) if rng.gen_bool(0.5) => (first, other),
+                    _ => (first, first),
+                }
+            };
+
+            let pair = provider
+                .lock()?
+                .get_code_pair(&[first], &[second], &mut random::lock(rng));
+            let (first, second) = match pair {
+                Ok(pair) => pair,
+                Err(GuessError::NoSnippet(_)) => continue,
+                Err(err) => return Err(err),
+            };
+
+            if let Some(round) = Self::parse_bonus_round(terminal, &first, &second)? {
+                return Ok(Some(round));
+            }
+        }
 
-        let highlighter = self.terminal.get_highlighter(&data.language);
-        let code = match self.terminal.parse_code(&data.code, highlighter, &width) {
+        Ok(None)
+    }
+
+    /// Parse two snippets into a [`RoundKind::SameLanguage`] round, with the
+    /// second one below the first after a blank line. This returns [`None`]
+    /// if either of them can't be played.
+    pub fn parse_bonus_round(
+        terminal: &Terminal,
+        first: &CodeData,
+        second: &CodeData,
+    ) -> Result<Option<Round>> {
+        // The snippets share the rows the code of a normal round would take
+        // up, minus the one between them.
+        let max_rows = Terminal::max_code_rows(2)?.saturating_sub(1) / 2;
+        let (first_code, second_code) = match (
+            Self::parse_snippet(terminal, first, max_rows.max(1))?,
+            Self::parse_snippet(terminal, second, max_rows.max(1))?,
+        ) {
+            (Some(first), Some(second)) => (first, second),
+            _ => return Ok(None),
+        };
+
+        let separator = CodeLine {
+            raw: String::from("\n"),
+            highlighted: String::new(),
+            tokens: Vec::new(),
+            is_continuation: false,
+        };
+
+        let is_same =
+            dialects::base_language(&first.language) == dialects::base_language(&second.language);
+
+        Ok(Some(Round {
+            kind: RoundKind::SameLanguage,
+            language: if is_same {
+                STRINGS.same_language.clone()
+            } else {
+                STRINGS.different_languages.clone()
+            },
+            code: first_code
+                .into_iter()
+                .chain([separator])
+                .chain(second_code)
+                .collect(),
+            options: vec![
+                STRINGS.same_language.clone(),
+                STRINGS.different_languages.clone(),
+            ],
+            full_code: Vec::new(),
+            source: None,
+            unavailable: Vec::new(),
+        }))
+    }
+
+    /// Whether the next round is a bonus round with `--bonus-rounds`.
+    #[must_use]
+    pub fn is_bonus_round(&self) -> bool {
+        ARGS.bonus_rounds && (self.accuracy.answered + 1) % BONUS_ROUND_INTERVAL == 0
+    }
+
+    /// Parse fetched code into a round, with options picked from `pool` with
+    /// `rng`. This returns [`None`] if the code can't be played, e.g. because
+    /// it's empty, only comments, or too ambiguous.
+    pub fn parse_round(
+        terminal: &Terminal,
+        data: &CodeData,
+        pool: &[&'static str],
+        rng: &mut StdRng,
+    ) -> Result<Option<Round>> {
+        let max_rows = Terminal::max_code_rows(Difficulty::current().option_count())?;
+
+        // If there is no valid code, or it's too ambiguous, another snippet is
+        // fetched.
+        let code = match Self::parse_snippet(terminal, data, max_rows)? {
             Some(code) => code,
-            // If there is no valid code, skip this round via recursion.
-            None => return self.start_new_round(preloader),
+            None => return Ok(None),
+        };
+
+        // With `--dialects`, the dialect has to be guessed instead of the
+        // language when it can be detected from the visible code.
+        let visible: String = code.iter().map(|line| line.raw.as_str()).collect();
+        let dialect = dialects::detect(&data.language, &visible).filter(|_| ARGS.dialects);
+
+        let (language, options) = match (ARGS.mode, dialect) {
+            // Code that can't be classified is skipped, like ambiguous code.
+            (GameMode::Category, _) => match categories::classify(&visible) {
+                Some(category) => (
+                    category.name.to_string(),
+                    categories::get_options(category, rng),
+                ),
+                None => return Ok(None),
+            },
+            (GameMode::Language | GameMode::Blitz, Some(dialect)) => (
+                dialect.name.to_string(),
+                dialects::get_options(dialect, rng),
+            ),
+            // Any selected language can be typed with expert, so they're all
+            // options, even though they aren't shown.
+            (GameMode::Language | GameMode::Blitz, None) if Difficulty::current().is_typed() => {
+                (data.language.clone(), Self::selected_languages())
+            }
+            (GameMode::Language | GameMode::Blitz, None) => (
+                data.language.clone(),
+                Self::get_options(
+                    &data.language,
+                    pool,
+                    Difficulty::current().option_count(),
+                    rng,
+                ),
+            ),
+        };
+
+        let full_code = if ARGS.review {
+            terminal.highlight_code(&data.code, terminal.get_code_highlighter(data))
+        } else {
+            Vec::new()
+        };
+
+        Ok(Some(Round {
+            kind: RoundKind::Guess,
+            language,
+            code,
+            options: options.into_iter().map(String::from).collect(),
+            full_code,
+            source: data.source.clone(),
+            unavailable: Vec::new(),
+        }))
+    }
+
+    /// Parse fetched code into at most `max_rows` rows. This returns [`None`]
+    /// if the code can't be played, e.g. because it's empty, only comments,
+    /// or too ambiguous.
+    pub fn parse_snippet(
+        terminal: &Terminal,
+        data: &CodeData,
+        max_rows: usize,
+    ) -> Result<Option<Vec<CodeLine>>> {
+        // Empty files are skipped right away instead of being parsed.
+        if data.is_blank() {
+            if ARGS.verbose {
+                let file = data.source.as_deref().unwrap_or(&data.language);
+                SKIPPED_FILES
+                    .lock()?
+                    .push(fill(&STRINGS.skipped_empty, file));
+            }
+
+            return Ok(None);
+        }
+
+        let width = Terminal::width()?;
+        let syntax = terminal.find_code_syntax(data);
+        let parse = |strip_comments| {
+            let highlighter = terminal.get_code_highlighter(data);
+            terminal.parse_code(&data.code, highlighter, &width, max_rows, strip_comments)
+        };
+
+        // Lines with a comment anywhere in them are dropped, so if every line
+        // was, the code is given another chance with comments kept rather
+        // than skipping a file that's commented throughout.
+        Ok(parse(true).or_else(|| parse(false)).filter(|code| {
+            terminal.count_distinct_tokens(code, syntax) >= ARGS.min_distinct_tokens
+        }))
+    }
+
+    /// Whether the time for `--mode blitz` has run out.
+    #[must_use]
+    pub fn is_time_up(&self) -> bool {
+        self.deadline
+            .map_or(false, |deadline| Instant::now() >= deadline)
+    }
+
+    /// Start a new round, which is called in the main function.
+    pub fn start_new_round(&mut self) -> Result<ControlFlow<()>> {
+        let round = if let Some(round) = self.prefetched.pop_front() {
+            round
+        } else {
+            let pool = self.language_pool();
+            let wanted = self.wanted_languages(&pool);
+            let is_bonus = self.is_bonus_round();
+            let preloader = self.preloader.as_mut();
+            let (provider, terminal, rng) = (&self.provider, &self.terminal, &self.rngs.content);
+            Self::fetch_round(provider, preloader, terminal, rng, &pool, &wanted, is_bonus)?
         };
 
-        let options = Self::get_options(&data.language);
+        self.play_round(round)
+    }
+
+    /// Wait for an answer or for the user to quit, returning it along with the
+    /// level of [`WAGER_PERCENTAGES`] that was chosen by pressing w. Pressing
+    /// h takes one of the `hints`. Digits without an option in `layout` are
+    /// ignored, and pausing is passed on through `sender`. Reaching `deadline`
+    /// counts as quitting.
+    pub fn read_answer(
+        terminal: &Terminal,
+        points: u32,
+        is_revealed: &AtomicBool,
+        layout: Layout,
+        sender: &Sender<RevealSignal>,
+        deadline: Option<Instant>,
+        hints: &mut RoundHints,
+    ) -> Result<(char, usize)> {
+        let mut wager_level = 0;
+
+        loop {
+            let input = Terminal::read_input_char(layout, deadline)?;
+
+            if input
+                .to_digit(10)
+                .map_or(false, |num| num as usize > layout.option_count)
+            {
+                continue;
+            }
+
+            // Revealing code can be paused, e.g. when interrupted, and may
+            // have already finished.
+            if input == 'p' {
+                let _ = sender.send(RevealSignal::TogglePause);
+                continue;
+            }
+
+            if input == 'w' {
+                if ARGS.wager {
+                    wager_level = (wager_level + 1) % WAGER_PERCENTAGES.len();
+                    terminal.print_wager(points, Self::get_wager(points, wager_level))?;
+                }
+
+                continue;
+            }
+
+            if input == 'h' {
+                if ARGS.hints {
+                    if let Some(available_points) = hints.take()? {
+                        terminal.print_hints(hints.taken(), available_points)?;
+                    }
+                }
+
+                continue;
+            }
 
-        if let Some(preloader) = preloader {
-            let _ = preloader.recv();
+            // Answers are ignored until the first line is revealed if blind
+            // guessing is disabled, but quitting is still allowed.
+            if ARGS.no_blind_guess && input.is_ascii_digit() && !is_revealed.load(Ordering::Relaxed)
+            {
+                continue;
+            }
+
+            return Ok((input, wager_level));
         }
+    }
+
+    /// Wait for the number of the option that was chosen, or typed with
+    /// `--difficulty expert`, along with the level of [`WAGER_PERCENTAGES`]
+    /// that was chosen. The number is [`None`] if the user quit or `deadline`
+    /// was reached. Once there's an answer, [`Terminal::start_showing_code`]
+    /// is told to stop revealing.
+    pub fn read_round_answer(
+        terminal: &Terminal,
+        options: &[&str],
+        points: u32,
+        is_revealed: &AtomicBool,
+        sender: &Sender<RevealSignal>,
+        deadline: Option<Instant>,
+        hints: &mut RoundHints,
+    ) -> Result<(Option<u32>, usize)> {
+        let (num, wager_level) = if Difficulty::current().is_typed() {
+            let idx = terminal.read_typed_answer(options, is_revealed, deadline)?;
+            (idx.map(|idx| idx as u32 + 1), 0)
+        } else {
+            let layout = terminal.layout()?;
+            let (input, wager_level) = Self::read_answer(
+                terminal,
+                points,
+                is_revealed,
+                layout,
+                sender,
+                deadline,
+                hints,
+            )?;
 
-        self.terminal
-            .print_round_info(&options, &code, &width, self.points)?;
+            // Quitting is the only input that isn't a digit.
+            (input.to_digit(10), wager_level)
+        };
+
+        let _ = sender.send(RevealSignal::Answered);
+
+        Ok((num, wager_level))
+    }
+
+    /// Render a round with its code hidden, returning when it was rendered.
+    pub fn show_round(&mut self, round: &Round) -> Result<Instant> {
+        let width = Terminal::width()?;
+        let options: Vec<_> = round.options.iter().map(String::as_str).collect();
+
+        // The languages of a bonus round aren't revealed, so they don't
+        // count.
+        if round.kind == RoundKind::Guess {
+            *self
+                .appearances
+                .entry(dialects::base_language(&round.language).to_string())
+                .or_default() += 1;
+        }
 
-        let available_points = Mutex::new(100.0);
+        for language in &round.unavailable {
+            *self.appearances.entry((*language).to_string()).or_default() += 1;
+        }
+
+        // Stale input is discarded before rendering rather than when waiting
+        // for input, so that fast players don't lose an answer given while
+        // the round is still being rendered.
+        Terminal::drain_events()?;
+
+        if ARGS.mode == GameMode::Blitz && self.deadline.is_none() {
+            self.deadline = Some(Instant::now() + BLITZ_TIME);
+        }
+
+        self.terminal.print_round_info(
+            &options,
+            &round.code,
+            &width,
+            self.points,
+            ARGS.accuracy.then_some(self.accuracy),
+            ARGS.streak_bonus
+                .then(|| scoring::streak_multiplier(self.accuracy.streak)),
+        )?;
+
+        if let Some(lives) = self.lives {
+            self.terminal.print_lives(lives)?;
+        }
+
+        if round.kind == RoundKind::SameLanguage {
+            self.terminal.print_bonus_prompt()?;
+        }
+
+        self.terminal.print_footer()?;
+
+        Ok(Instant::now())
+    }
+
+    /// Show a round and wait for the user to answer.
+    pub fn play_round(&mut self, round: Round) -> Result<ControlFlow<()>> {
+        let rendered_at = self.show_round(&round)?;
+        let answered = self.accuracy.answered;
+        let options: Vec<_> = round.options.iter().map(String::as_str).collect();
+        let (code, language) = (&round.code, &round.language);
+
+        let available_points = Mutex::new(Difficulty::current().starting_points());
+        let is_revealed = AtomicBool::new(false);
+        let mut is_missed = false;
         let (sender, receiver) = mpsc::channel();
+        let (timer_sender, timer_receiver) = mpsc::channel();
+        let deadline = self.deadline;
 
         // [`Terminal::start_showing_code`] and [`Terminal::read_input_char`]
-        // both create blocking loops, so they have to be used in separate threads.
-        thread::scope(|s| {
-            let display = s.spawn(|| {
-                self.terminal
-                    .start_showing_code(&code, &available_points, receiver)
+        // both create blocking loops, so they have to be used in separate
+        // threads, as does the clock of blitz mode, which stops once
+        // `timer_sender` is dropped.
+        let result = thread::scope(|s| {
+            let timer = s.spawn(|| self.terminal.start_blitz_timer(deadline, timer_receiver));
+
+            let display = s.spawn(|| match ARGS.study_time {
+                Some(study_time) => self.terminal.start_study_timer(
+                    code,
+                    &available_points,
+                    &is_revealed,
+                    receiver,
+                    study_time,
+                ),
+                None => self.terminal.start_showing_code(
+                    code,
+                    &available_points,
+                    &is_revealed,
+                    receiver,
+                    &self.rngs.display,
+                ),
             });
 
             let input = s.spawn(|| {
-                let input = Terminal::read_input_char()?;
-
-                // Notifies [`Terminal::start_showing_code`] to not show the
-                // next line.
                 let sender = sender;
-                let _ = sender.send(());
+                let mut hints = RoundHints::new(language, &available_points);
+                let (num, wager_level) = Self::read_round_answer(
+                    &self.terminal,
+                    &options,
+                    self.points,
+                    &is_revealed,
+                    &sender,
+                    deadline,
+                    &mut hints,
+                )?;
 
-                if input == 'q' || input == 'c' {
-                    Ok(ControlFlow::Break(()))
-                } else {
-                    let result = self.terminal.process_input(
-                        input.to_digit(10).ok_or("invalid input")?,
+                if let Some(num) = num {
+                    let stakes = Self::get_stakes(
+                        self.points,
+                        wager_level,
+                        rendered_at,
+                        self.accuracy,
+                        round.kind,
+                    );
+                    let points_before = self.points;
+                    let flow = self.terminal.process_input(
+                        num,
                         &options,
-                        &data.language,
+                        language,
                         &available_points,
                         &mut self.points,
-                    );
+                        &stakes,
+                    )?;
+
+                    self.records.push(RoundRecord {
+                        language: language.clone(),
+                        options: round.options.clone(),
+                        answer: options[num as usize - 1].to_string(),
+                        was_correct: flow.is_continue(),
+                        points: i64::from(self.points) - i64::from(points_before),
+                    });
+
+                    let shown = Self::shown_options(&options, num);
+                    Self::after_answer(&self.terminal, &mut self.streaks, &round, shown, flow)?;
+                    self.accuracy.record(flow.is_continue());
 
                     // Let the user visually process the result. If they got it
                     // correct, the timer is set after a thread is spawned to
                     // preload the next round's gist.
-                    if let Ok(ControlFlow::Break(())) = result {
-                        thread::sleep(Duration::from_millis(1500));
+                    if flow.is_break() {
+                        is_missed = Self::after_wrong_answer(
+                            &self.terminal,
+                            &mut self.missed,
+                            &mut self.lives,
+                            language,
+                        )?;
+                        if is_missed {
+                            return Ok(ControlFlow::Continue(()));
+                        }
                     }
 
-                    result
+                    Ok(flow)
+                } else {
+                    Ok(ControlFlow::Break(()))
                 }
             });
 
             display.join().unwrap()?;
-            input.join().unwrap()
+            let result = input.join().unwrap();
+            drop(timer_sender);
+            timer.join().unwrap()?;
+            result
+        });
+
+        if ARGS.review && self.accuracy.answered > answered {
+            self.terminal.print_review(&round)?;
+        }
+
+        self.last_round = Some(round);
+
+        if let Ok(ControlFlow::Continue(())) = result {
+            if !is_missed {
+                self.rounds += 1;
+            }
+
+            self.save_checkpoint();
+        }
+
+        result
+    }
+
+    /// Explain the answer to `round` if needed, and update the streak of its
+    /// language. Neither is done for bonus rounds, whose answers aren't
+    /// languages.
+    pub fn after_answer(
+        terminal: &Terminal,
+        streaks: &mut HashMap<String, u32>,
+        round: &Round,
+        shown: &[&str],
+        flow: ControlFlow<()>,
+    ) -> Result<()> {
+        if round.kind == RoundKind::SameLanguage {
+            return Ok(());
+        }
+
+        Self::explain_answer(terminal, &round.language, shown, &round.code, flow)?;
+
+        let streak = streaks
+            .entry(dialects::base_language(&round.language).to_string())
+            .or_default();
+        *streak = if flow.is_continue() { *streak + 1 } else { 0 };
+
+        Ok(())
+    }
+
+    /// Take one of the `lives` left and let the user visually process a wrong
+    /// answer, then return whether the game goes on anyway. With `--lives`,
+    /// it does until none are left. Otherwise, it does when practicing wrong
+    /// answers, in which case `language` is queued in `missed`, and in blitz
+    /// mode, where only the clock ends the game.
+    pub fn after_wrong_answer(
+        terminal: &Terminal,
+        missed: &mut VecDeque<(&'static str, u32)>,
+        lives: &mut Option<u32>,
+        language: &str,
+    ) -> Result<bool> {
+        if let Some(lives) = lives {
+            *lives = lives.saturating_sub(1);
+            terminal.print_lives(*lives)?;
+        }
+
+        // Leave more time to read the note when teaching.
+        thread::sleep(if ARGS.teach || ARGS.explain || ARGS.feedback {
+            NOTE_PAUSE
+        } else {
+            RESULT_PAUSE
+        });
+
+        if ARGS.practice_wrong {
+            Self::queue_missed(missed, language);
+        }
+
+        Ok(match lives {
+            Some(lives) => *lives > 0,
+            None => ARGS.practice_wrong || ARGS.mode == GameMode::Blitz,
         })
     }
 
     /// Wait 1.5 seconds for the user to visually process they got the right
     /// answer while the next round is preloading, then start the next round.
+    /// If `l` is pressed in the meantime, the last round is replayed first.
+    /// Preloading is paused when the ratelimit is nearly exhausted (see
+    /// [`PRELOAD_RATELIMIT_THRESHOLD`]), so that the game doesn't ratelimit
+    /// itself.
     pub fn start_next_round(&mut self) -> Result<ControlFlow<()>> {
-        let (sender, receiver) = mpsc::channel();
+        // With `--url`, there's only one round to play.
+        let is_over = ARGS.url.is_some()
+            || self.is_time_up()
+            || ARGS
+                .max_rounds
+                .map_or(false, |max_rounds| self.accuracy.answered >= max_rounds);
+
+        if is_over || !self.prefetched.is_empty() {
+            self.is_complete = is_over;
+            Self::pause_between_rounds(&self.terminal, self.last_round.as_ref(), self.points)?;
 
-        thread::scope(|s| {
-            let handle = s.spawn(|| self.start_new_round(Some(receiver)));
+            return match self.prefetched.pop_front() {
+                Some(round) if !is_over => self.play_round(round),
+                _ => Ok(ControlFlow::Break(())),
+            };
+        }
 
-            thread::sleep(Duration::from_millis(1500));
+        let pool = self.language_pool();
+        let wanted = self.wanted_languages(&pool);
+        let is_bonus = self.is_bonus_round();
+        // The preloader's worker keeps the provider locked while fetching, in
+        // which case it checks the ratelimit itself.
+        let remaining = self
+            .provider
+            .try_lock()
+            .ok()
+            .and_then(|provider| provider.ratelimit_remaining())
+            .filter(|&remaining| remaining < PRELOAD_RATELIMIT_THRESHOLD);
 
-            // Clear the screen and move to the top right corner. This is not
-            // a method of [`Terminal`] because it would take a lot of work to
-            // let the borrow checker let me use `self` again.
-            let _clear = execute!(stdout().lock(), Clear(ClearType::All), MoveTo(0, 0));
-            let _ = sender.send(());
+        let round = match remaining {
+            None => thread::scope(|s| {
+                let (provider, terminal, rng) =
+                    (&self.provider, &self.terminal, &self.rngs.content);
+                let preloader = self.preloader.as_mut();
+                let handle = s.spawn(move || {
+                    Self::fetch_round(provider, preloader, terminal, rng, &pool, &wanted, is_bonus)
+                });
 
-            handle.join().unwrap()
-        })
+                Self::pause_between_rounds(&self.terminal, self.last_round.as_ref(), self.points)?;
+                handle.join().unwrap()
+            })?,
+            Some(remaining) => {
+                self.terminal.print_ratelimit_warning(remaining)?;
+                Self::pause_between_rounds(&self.terminal, self.last_round.as_ref(), self.points)?;
+                let (provider, terminal, rng) =
+                    (&self.provider, &self.terminal, &self.rngs.content);
+                Self::fetch_round(provider, None, terminal, rng, &pool, &wanted, is_bonus)?
+            }
+        };
+
+        self.play_round(round)
+    }
+
+    /// Show the replay option for `--continue-delay` ms (or until a key is
+    /// pressed with `--instant`) and replay `last_round` if it's chosen, then
+    /// clear the screen for the next round. The next round keeps preloading
+    /// in the meantime, so continuing early only waits for it if needed. With
+    /// `--review`, the round was just reviewed, so the screen is cleared
+    /// right away.
+    pub fn pause_between_rounds(
+        terminal: &Terminal,
+        last_round: Option<&Round>,
+        points: u32,
+    ) -> Result<()> {
+        if ARGS.review {
+            let mut writer = terminal.lock()?;
+            return execute!(writer, Clear(ClearType::All), MoveTo(0, 0)).map_err(Into::into);
+        }
+
+        terminal.print_replay_option()?;
+
+        let timeout = if ARGS.instant {
+            None
+        } else {
+            Some(Duration::from_millis(ARGS.continue_delay))
+        };
+
+        if Terminal::wait_for_key('l', timeout)? {
+            if let Some(round) = last_round {
+                Self::replay_round(terminal, round, points)?;
+                thread::sleep(RESULT_PAUSE);
+            }
+        }
+
+        // Clear the screen and move to the top right corner.
+        let mut writer = terminal.lock()?;
+        execute!(writer, Clear(ClearType::All), MoveTo(0, 0)).map_err(Into::into)
+    }
+
+    /// Re-render a round and reveal all of its code again. No points are
+    /// awarded.
+    pub fn replay_round(terminal: &Terminal, round: &Round, points: u32) -> Result<()> {
+        let width = Terminal::width()?;
+        let options: Vec<_> = round.options.iter().map(String::as_str).collect();
+
+        let mut writer = terminal.lock()?;
+        execute!(writer, Clear(ClearType::All), MoveTo(0, 0))?;
+        drop(writer);
+
+        terminal.print_round_info(&options, &round.code, &width, points, None, None)?;
+        terminal.replay_code(&round.code)
+    }
+}
+
+/// The number of single character insertions, deletions, or substitutions
+/// needed to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn language_cues() {
+        let width = 500;
+        let terminal = Terminal::with_writer(Vec::new()).unwrap();
+        let code = terminal
+            .parse_code(
+                "fn main() {\n    let mut fnmatch = std::env::args();\n}\n",
+                terminal.get_highlighter("Rust"),
+                &width,
+                usize::MAX,
+                true,
+            )
+            .unwrap();
+
+        assert_eq!(Game::get_cues("Rust", &code), ["fn", "let mut", "::"]);
+        assert!(Game::get_cues("SQL", &code).is_empty());
+        assert!(LANGUAGES
+            .iter()
+            .all(|language| LANGUAGE_CUES.iter().any(|(name, _)| name == language)));
+    }
+
+    #[test]
+    fn reasonable_options() {
+        let width = 500;
+        let terminal = Terminal::with_writer(Vec::new()).unwrap();
+        let code = terminal
+            .parse_code(
+                "fn main() {\n    let mut args = std::env::args();\n}\n",
+                terminal.get_highlighter("Rust"),
+                &width,
+                usize::MAX,
+                true,
+            )
+            .unwrap();
+
+        let feedback = Game::get_feedback(&["SQL", "C++", "Rust", "Go"], "Rust", &code);
+        assert_eq!(feedback, [("C++", vec!["std::"])]);
+    }
+
+    #[test]
+    fn speed_bonus() {
+        assert_eq!(Game::get_speed_bonus(20, Duration::ZERO), 20);
+        assert_eq!(Game::get_speed_bonus(20, SPEED_BONUS_WINDOW / 2), 10);
+        assert_eq!(Game::get_speed_bonus(20, SPEED_BONUS_WINDOW * 2), 0);
+        assert_eq!(Game::get_speed_bonus(1000, Duration::ZERO), MAX_SPEED_BONUS);
+    }
+
+    #[test]
+    fn balanced_languages() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut appearances = HashMap::new();
+
+        // The languages are picked the way a provider would, but some of them
+        // are never found, like with gists, in which case any language is
+        // played instead (see [`Game::fetch_round`]).
+        let is_found = |language: &&str| !["MATLAB", "R"].contains(language);
+        let found: Vec<_> = LANGUAGES.into_iter().filter(is_found).collect();
+
+        for _ in 0..LANGUAGES.len() * 100 {
+            let wanted = Game::balanced_languages(&LANGUAGES, &appearances);
+            let candidates: Vec<_> = wanted.iter().copied().filter(is_found).collect();
+
+            let language = if let Some(language) = candidates.choose(&mut rng) {
+                *language
+            } else {
+                for language in wanted {
+                    *appearances.entry(language.to_string()).or_default() += 1;
+                }
+
+                *found.choose(&mut rng).unwrap()
+            };
+            *appearances.entry(language.to_string()).or_default() += 1;
+        }
+
+        let count = |language: &str| appearances.get(language).copied().unwrap_or_default();
+        let counts: Vec<u32> = found.iter().map(|language| count(language)).collect();
+
+        let (min, max) = (counts.iter().min().unwrap(), counts.iter().max().unwrap());
+        assert!(max - min <= BALANCE_SLACK + 1, "{counts:?}");
+    }
+
+    /// Returns the same code for every request, written in one of the
+    /// requested languages picked at random.
+    struct AnyLanguageProvider;
+
+    impl GithubProvider for AnyLanguageProvider {
+        fn new() -> Result<Self> {
+            Ok(Self)
+        }
+
+        fn get_code(&mut self, languages: &[&'static str], rng: &mut StdRng) -> Result<CodeData> {
+            Ok(CodeData {
+                code: String::from("let total = count + 1;\nprint(total);\n"),
+                language: (*languages.choose(rng).unwrap()).to_string(),
+                content_type: None,
+                source: None,
+            })
+        }
+    }
+
+    /// Create a game that gets its code from `provider`, without a preloader.
+    fn test_game(provider: impl GithubProvider + 'static) -> Game {
+        Game {
+            points: 0,
+            terminal: Terminal::with_writer(Box::new(Vec::new()) as Box<dyn std::io::Write + Send>)
+                .unwrap(),
+            provider: Arc::new(Mutex::new(Box::new(provider))),
+            preloader: None,
+            streaks: HashMap::new(),
+            last_round: None,
+            rounds: 0,
+            appearances: HashMap::new(),
+            accuracy: Accuracy::default(),
+            prefetched: VecDeque::new(),
+            missed: VecDeque::new(),
+            is_complete: false,
+            records: Vec::new(),
+            deadline: None,
+            lives: None,
+            rngs: Rngs::new(0),
+        }
+    }
+
+    #[test]
+    fn no_repeated_languages() {
+        let mut game = test_game(AnyLanguageProvider);
+
+        for _ in 0..100 {
+            let pool = game.language_pool();
+            let wanted = game.wanted_languages(&pool);
+            let round = Game::fetch_round(
+                &game.provider,
+                None,
+                &game.terminal,
+                &game.rngs.content,
+                &pool,
+                &wanted,
+                false,
+            )
+            .unwrap();
+
+            if let Some(last_round) = &game.last_round {
+                assert_ne!(round.language, last_round.language);
+            }
+            game.last_round = Some(round);
+        }
+    }
+
+    #[test]
+    fn resume_checkpoint() {
+        let mut game = test_game(AnyLanguageProvider);
+        game.points = 420;
+        game.rounds = 7;
+        game.streaks.insert(String::from("Rust"), 3);
+        game.accuracy = Accuracy {
+            correct: 7,
+            answered: 9,
+            streak: 3,
+        };
+
+        let path = std::env::temp_dir().join(format!(
+            "guess-that-lang-checkpoint-{}.toml",
+            std::process::id()
+        ));
+        confy::store_path(&path, game.checkpoint()).unwrap();
+        let checkpoint: Checkpoint = confy::load_path(&path).unwrap();
+
+        assert_eq!(checkpoint.seed, Some(random::seed().to_string()));
+
+        // Seeds can be larger than TOML integers.
+        let large = Checkpoint {
+            seed: Some(u64::MAX.to_string()),
+            ..Checkpoint::default()
+        };
+        confy::store_path(&path, large).unwrap();
+        let large: Checkpoint = confy::load_path(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(large.seed, Some(u64::MAX.to_string()));
+
+        let mut resumed = test_game(AnyLanguageProvider);
+        resumed.restore(checkpoint);
+
+        assert_eq!(resumed.points, 420);
+        assert_eq!(resumed.rounds, 7);
+        assert_eq!(resumed.streaks, game.streaks);
+        assert_eq!(resumed.accuracy, game.accuracy);
+    }
+
+    #[test]
+    fn missed_language_reappears() {
+        let mut missed = VecDeque::new();
+        Game::queue_missed(&mut missed, "Python 2");
+        assert_eq!(missed, [("Python", PRACTICE_DELAY)]);
+
+        let reappeared =
+            (0..=PRACTICE_DELAY).position(|_| Game::next_missed(&mut missed).is_some());
+
+        assert_eq!(reappeared, Some(PRACTICE_DELAY as usize - 1));
+        assert!(missed.is_empty());
+
+        missed.extend([("Rust", PRACTICE_DELAY), ("Go", PRACTICE_DELAY + 1)]);
+        let languages: Vec<_> = (0..4).map(|_| Game::next_missed(&mut missed)).collect();
+        assert_eq!(languages, [None, Some("Rust"), Some("Go"), None]);
+    }
+
+    #[test]
+    fn difficulty_options() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+            let options =
+                Game::get_options("Rust", &LANGUAGES, difficulty.option_count(), &mut rng);
+
+            assert_eq!(options.len(), difficulty.option_count());
+            assert!(options.contains(&"Rust"));
+        }
+
+        assert_eq!(Difficulty::Easy.option_count(), 3);
+        assert_eq!(Difficulty::Easy.reveal_interval(), 2500);
+        assert_eq!(Difficulty::Hard.reveal_interval(), 1000);
+
+        // Small pools are filled up with other languages.
+        assert_eq!(
+            Game::get_options("Rust", &["Rust", "Go"], 6, &mut rng).len(),
+            6
+        );
+        assert_eq!("hard".parse(), Ok(Difficulty::Hard));
+        assert_eq!("expert".parse(), Ok(Difficulty::Expert));
+        assert!(Difficulty::Expert.is_typed());
+        assert!("impossible".parse::<Difficulty>().is_err());
+    }
+
+    #[test]
+    fn accuracy_display() {
+        let mut accuracy = Accuracy::default();
+        assert_eq!(accuracy.to_string(), "-");
+
+        for is_correct in [true, false, true] {
+            accuracy.record(is_correct);
+        }
+
+        assert_eq!(accuracy.to_string(), "67% (2/3)");
+    }
+
+    #[test]
+    fn narrow_pool_never_empty() {
+        assert_eq!(Game::narrow_pool(vec!["Rust"], |_| true), vec!["Rust"]);
+    }
+
+    #[test]
+    fn parse_languages() {
+        assert_eq!(
+            Game::parse_languages("rust, Go,,c++,RUST").unwrap(),
+            ["Rust", "Go", "C++"]
+        );
+
+        let error = Game::parse_languages("Rust,Pyhton")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("\"Pyhton\""));
+        assert!(error.contains("Did you mean \"Python\"?"));
+        assert!(error.ends_with("Valid languages: Assembly, Shell, C, C#, C++, CSS, Dart, Dockerfile, Go, Groovy, HTML, Java, JavaScript, Kotlin, Lua, MATLAB, PHP, PowerShell, Python, R, Ruby, Rust, SQL, Swift, TypeScript"));
+
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "go"), 2);
+    }
+
+    #[test]
+    fn skip_blank_code() {
+        let terminal =
+            Terminal::with_writer(Box::new(Vec::new()) as Box<dyn std::io::Write + Send>).unwrap();
+        let data = CodeData {
+            code: String::from("\n  \n"),
+            language: String::from("Rust"),
+            content_type: None,
+            source: None,
+        };
+
+        assert!(
+            Game::parse_round(&terminal, &data, &LANGUAGES, &mut StdRng::seed_from_u64(0))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn bonus_rounds_double_points() {
+        let stakes = Stakes {
+            speed_bonus: 5,
+            is_bonus: true,
+            ..Stakes::default()
+        };
+
+        assert_eq!(stakes.points_won(true, 50.0), 105);
+        assert_eq!(stakes.points_won(false, 50.0), 0);
+        assert_eq!(
+            Stakes::default().points_won(true, 50.0),
+            50,
+            "normal rounds aren't doubled"
+        );
     }
 }
@@ -0,0 +1,151 @@
+use std::sync::Mutex;
+
+use crate::{
+    dialects,
+    strings::{fill, STRINGS},
+    Result,
+};
+
+/// How many available points each hint costs.
+pub const HINT_COST: f32 = 20.0;
+
+/// What can be revealed about a language with `--hints`, from what gives it
+/// away the least to what gives it away the most.
+pub struct LanguageHints {
+    pub language: &'static str,
+    /// What kind of language it is.
+    pub paradigm: &'static str,
+    /// A keyword or piece of syntax that's characteristic of it.
+    pub keyword: &'static str,
+    /// The extension or name of its files.
+    pub file: &'static str,
+}
+
+/// The hints for every language in [`crate::game::LANGUAGES`].
+pub const HINTS: [LanguageHints; 25] = [
+    hints("Assembly", "low-level, imperative", "mov", "*.asm"),
+    hints("Shell", "command language, scripting", "fi", "*.sh"),
+    hints("C", "procedural, compiled", "typedef struct", "*.c"),
+    hints("C#", "object-oriented, .NET", "namespace", "*.cs"),
+    hints("C++", "multi-paradigm, systems", "std::", "*.cpp"),
+    hints("CSS", "style sheet", "!important", "*.css"),
+    hints("Dart", "object-oriented, client-side", "late", "*.dart"),
+    hints("Dockerfile", "container build file", "FROM", "Dockerfile"),
+    hints("Go", "concurrent, compiled", "func", "*.go"),
+    hints("Groovy", "dynamic, JVM", "def", "*.groovy"),
+    hints("HTML", "markup", "<div>", "*.html"),
+    hints("Java", "object-oriented, JVM", "public static", "*.java"),
+    hints("JavaScript", "dynamic, event-driven", "const", "*.js"),
+    hints("Kotlin", "multi-paradigm, JVM", "fun", "*.kt"),
+    hints("Lua", "embeddable, scripting", "local", "*.lua"),
+    hints("MATLAB", "numerical computing", "end", "*.m"),
+    hints("PHP", "server-side, scripting", "$this->", "*.php"),
+    hints("PowerShell", "command language, .NET", "param", "*.ps1"),
+    hints("Python", "multi-paradigm, dynamic", "def", "*.py"),
+    hints("R", "statistical computing", "<-", "*.R"),
+    hints("Ruby", "object-oriented, dynamic", "end", "*.rb"),
+    hints("Rust", "systems, memory-safe", "fn", "*.rs"),
+    hints("SQL", "declarative, query", "SELECT", "*.sql"),
+    hints("Swift", "multi-paradigm, compiled", "guard", "*.swift"),
+    hints("TypeScript", "typed, event-driven", "interface", "*.ts"),
+];
+
+const fn hints(
+    language: &'static str,
+    paradigm: &'static str,
+    keyword: &'static str,
+    file: &'static str,
+) -> LanguageHints {
+    LanguageHints {
+        language,
+        paradigm,
+        keyword,
+        file,
+    }
+}
+
+/// Get the hints for the language of an answer, in the order they're
+/// revealed. Dialects share the hints of their language, and there are none
+/// for unknown languages.
+#[must_use]
+pub fn get_hints(answer: &str) -> Vec<String> {
+    let language = dialects::base_language(answer);
+
+    HINTS
+        .iter()
+        .find(|hints| hints.language == language)
+        .map_or_else(Vec::new, |hints| {
+            vec![
+                fill(&STRINGS.hint_paradigm, hints.paradigm),
+                fill(&STRINGS.hint_keyword, hints.keyword),
+                fill(&STRINGS.hint_file, hints.file),
+            ]
+        })
+}
+
+/// The hints of a round and how many of them were taken by pressing h.
+pub struct RoundHints<'a> {
+    pub hints: Vec<String>,
+    pub taken: usize,
+    /// The available points of the round, which pay for the hints.
+    pub available_points: &'a Mutex<f32>,
+}
+
+impl<'a> RoundHints<'a> {
+    #[must_use]
+    pub fn new(answer: &str, available_points: &'a Mutex<f32>) -> Self {
+        Self {
+            hints: get_hints(answer),
+            taken: 0,
+            available_points,
+        }
+    }
+
+    /// Take the next hint for [`HINT_COST`] points, returning the available
+    /// points that are left. Nothing is taken if there are no hints left or
+    /// not enough points to pay for one.
+    pub fn take(&mut self) -> Result<Option<f32>> {
+        let mut available_points = self.available_points.lock()?;
+        if self.taken == self.hints.len() || *available_points < HINT_COST {
+            return Ok(None);
+        }
+
+        self.taken += 1;
+        *available_points -= HINT_COST;
+
+        Ok(Some(*available_points))
+    }
+
+    /// The hints that were taken so far.
+    #[must_use]
+    pub fn taken(&self) -> &[String] {
+        &self.hints[..self.taken]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::LANGUAGES;
+
+    #[test]
+    fn every_language_has_hints() {
+        for language in LANGUAGES {
+            assert_eq!(get_hints(language).len(), 3, "{language}");
+        }
+
+        assert!(get_hints("Brainfuck").is_empty());
+    }
+
+    #[test]
+    fn hints_cost_points() {
+        let available_points = Mutex::new(50.0);
+        let mut hints = RoundHints::new("Rust", &available_points);
+
+        assert_eq!(hints.take().unwrap(), Some(30.0));
+        assert_eq!(hints.take().unwrap(), Some(10.0));
+        assert_eq!(hints.take().unwrap(), None);
+        assert_eq!(hints.taken().len(), 2);
+        assert!(hints.taken()[1].contains("fn"));
+    }
+}
@@ -3,68 +3,549 @@
 #![allow(
     clippy::cast_possible_truncation,
     clippy::cast_sign_loss,
+    clippy::format_collect,
     clippy::missing_errors_doc,
-    clippy::missing_panics_doc
+    clippy::missing_panics_doc,
+    clippy::multiple_crate_versions
 )]
 
-use std::{error::Error, ops::ControlFlow, result};
+use std::{env, ops::ControlFlow, path::PathBuf, process, result};
 
 use argh::FromArgs;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 
+pub mod categories;
+pub mod challenge;
+pub mod dialects;
+pub mod error;
+pub mod export;
 pub mod game;
+pub mod hints;
 pub mod providers;
+pub mod random;
+pub mod scoring;
+pub mod setup;
+pub mod stats;
+pub mod strings;
 pub mod terminal;
 
-use crate::{game::Game, terminal::ThemeStyle};
+use crate::{
+    challenge::Challenge,
+    error::GuessError,
+    game::{Difficulty, Game, GameMode},
+    stats::{LegacyStats, Stats},
+    terminal::{Palette, RevealMode, ThemeStyle},
+};
 
-pub type Result<T> = result::Result<T, Box<dyn Error + Send + Sync>>;
+pub type Result<T> = result::Result<T, GuessError>;
+
+/// The number of ms to wait before revealing code when it isn't given with
+/// `--wait` or chosen with `--setup`.
+pub const DEFAULT_WAIT: u64 = 1500;
 
 /// CLI game to see how fast you can guess the language of a code block!
 #[derive(FromArgs)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Args {
     /// your personal access token
     #[argh(short = 't', option)]
     token: Option<String>,
 
-    /// where to get the code from (gists/repos)
+    /// a comma separated list of tokens to rotate through when one is ratelimited
+    #[argh(option)]
+    tokens: Option<String>,
+
+    /// your GitLab personal access token, for the gitlab provider
+    #[argh(option)]
+    gitlab_token: Option<String>,
+
+    /// where to get the code from (gists/repos/trending/local/gitlab/
+    /// sourcegraph), which defaults to repos or the one chosen with --setup.
+    /// Several can be mixed by separating them with commas, with optional
+    /// weights like repos:70,gists:30
     #[argh(short = 'p', option)]
     provider: Option<String>,
 
-    /// the number of ms to wait before revealing code
-    #[argh(short = 'w', option, default = "1500")]
-    wait: u64,
+    /// list the providers that can be chosen with --provider, instead of
+    /// playing
+    #[argh(switch)]
+    list_providers: bool,
+
+    /// a directory, file, or glob pattern (like "src/**/*.rs") the local
+    /// provider gets code from, which can be given more than once
+    #[argh(option)]
+    path: Vec<String>,
+
+    /// only use repos pushed to within this long (e.g. 30d, 2w, 6m, 1y)
+    #[argh(option)]
+    max_file_age: Option<String>,
+
+    /// how many days back the trending provider looks for new repos
+    #[argh(option, default = "30")]
+    since: u32,
+
+    /// the number of ms to wait before revealing code (default: 1500, or
+    /// the one chosen with --setup)
+    #[argh(short = 'w', option)]
+    wait: Option<u64>,
+
+    /// wrap lines too wide for the terminal onto more rows instead of cutting
+    /// them off
+    #[argh(switch)]
+    wrap: bool,
+
+    /// the most non-empty lines of code to show, which is lowered if they
+    /// don't fit in the terminal
+    #[argh(option, default = "10")]
+    max_lines: u8,
+
+    /// the number of ms to wait between revealing each step of the code after
+    /// the first, which depends on the difficulty by default
+    #[argh(option)]
+    reveal_interval: Option<u64>,
+
+    /// the number of ms to wait after a correct answer before the next round
+    #[argh(option, default = "1500")]
+    continue_delay: u64,
+
+    /// go to the next round as soon as a key is pressed after a correct answer
+    #[argh(switch)]
+    instant: bool,
+
+    /// after each answer, show all of the code and a link to where it's from
+    /// until a key is pressed
+    #[argh(switch)]
+    review: bool,
+
+    /// the preferred minimum size of files from the repos provider, in bytes
+    #[argh(option, default = "500")]
+    min_file_size: u64,
+
+    /// the preferred maximum size of files from the repos provider, in bytes
+    #[argh(option, default = "20000")]
+    max_file_size: u64,
+
+    /// the minimum number of ms between search requests from the repos provider
+    #[argh(option, default = "2000")]
+    search_interval: u64,
+
+    /// get files from the repos provider with Github's GraphQL API, which takes
+    /// fewer requests (requires a token)
+    #[argh(switch)]
+    graphql: bool,
+
+    /// reveal all code at once and let points decay over this many seconds
+    #[argh(option)]
+    study_time: Option<u16>,
+
+    /// the marker shown at the end of lines too wide for the terminal
+    #[argh(option, default = "String::from(\"...\")")]
+    truncation_marker: String,
+
+    /// allow the same language to be used twice in a row
+    #[argh(switch)]
+    allow_repeat_language: bool,
+
+    /// what to guess about the code (language/category), or blitz to guess
+    /// languages against the clock
+    #[argh(option, default = "GameMode::Language")]
+    mode: GameMode,
+
+    /// how many options there are and how fast code is revealed
+    /// (easy/normal/hard/expert), which is remembered for next time
+    #[argh(option)]
+    difficulty: Option<Difficulty>,
+
+    /// how to reveal code (lines/bottom-up/tokens)
+    #[argh(option, default = "RevealMode::Lines")]
+    reveal: RevealMode,
+
+    /// skip snippets with fewer distinct keywords and operators than this
+    #[argh(option, default = "0")]
+    min_distinct_tokens: usize,
+
+    /// list the fetched files that were skipped, like empty ones, when the
+    /// game ends
+    #[argh(switch)]
+    verbose: bool,
+
+    /// include test files from the repos provider
+    #[argh(switch)]
+    include_tests: bool,
+
+    /// show the answer choices above the code instead of below it
+    #[argh(switch)]
+    options_first: bool,
 
     /// whether or not to reveal lines in random order
     #[argh(short = 's', switch)]
     shuffle: bool,
 
-    /// whether to use dark or light theme (dark/light)
+    /// ignore answers until the first line of code is revealed
+    #[argh(switch)]
+    no_blind_guess: bool,
+
+    /// play languages at random instead of favoring the ones that have
+    /// appeared the least this session
+    #[argh(switch)]
+    unbalanced: bool,
+
+    /// keep playing after a wrong answer, and replay the missed language soon
+    #[argh(switch)]
+    practice_wrong: bool,
+
+    /// allow answering by clicking an option
+    #[argh(switch)]
+    mouse: bool,
+
+    /// show how many points revealing each line of code costs
+    #[argh(switch)]
+    show_points_per_line: bool,
+
+    /// show the percentage of rounds answered correctly this session
+    #[argh(switch)]
+    accuracy: bool,
+
+    /// end the game after this many rounds
+    #[argh(option)]
+    max_rounds: Option<u32>,
+
+    /// end the game after this many wrong answers instead of the first one
+    #[argh(option)]
+    lives: Option<u32>,
+
+    /// fetch every round before starting, so no requests are made while
+    /// playing (requires --max-rounds)
+    #[argh(switch)]
+    prefetch: bool,
+
+    /// forget which snippets were played in earlier games
+    #[argh(switch)]
+    forget: bool,
+
+    /// stop using a language after it's guessed correctly this many times in a row
+    #[argh(option)]
+    master_after: Option<u32>,
+
+    /// award up to this many extra points (at most 50) for answering quickly
+    #[argh(option)]
+    speed_bonus: Option<u32>,
+
+    /// multiply the points awarded for answering correctly several times in a
+    /// row
+    #[argh(switch)]
+    streak_bonus: bool,
+
+    /// allow wagering points on each answer by pressing w
+    #[argh(switch)]
+    wager: bool,
+
+    /// allow revealing hints about the language for 20 points each by
+    /// pressing h
+    #[argh(switch)]
+    hints: bool,
+
+    /// make every fifth round a bonus round, where two snippets are shown and
+    /// guessing whether they're the same language is worth double points
+    #[argh(switch)]
+    bonus_rounds: bool,
+
+    /// guess the dialect (e.g. Python 2/3) instead of the language when possible
+    #[argh(switch)]
+    dialects: bool,
+
+    /// explain how the languages are related after a wrong answer
+    #[argh(switch)]
+    teach: bool,
+
+    /// don't show the link to share a new high score
+    #[argh(switch)]
+    no_share: bool,
+
+    /// show which parts of the code gave the language away after each round
+    #[argh(switch)]
+    explain: bool,
+
+    /// after a wrong answer, show which other options the code had cues for
+    #[argh(switch)]
+    feedback: bool,
+
+    /// a TOML file with translations of the game's text
+    #[argh(option)]
+    lang_file: Option<String>,
+
+    /// whether to use dark or light theme (dark/light/auto), or the name of
+    /// any bundled theme
     #[argh(option)]
     theme: Option<String>,
+
+    /// the colors for points and answers (normal/colorblind/mono), which is
+    /// remembered
+    #[argh(option)]
+    palette: Option<Palette>,
+
+    /// a .tmTheme file to use as the theme
+    #[argh(option)]
+    theme_file: Option<String>,
+
+    /// continue the last game if it exited unexpectedly
+    #[argh(switch)]
+    resume: bool,
+
+    /// store the resolved theme so it stays the same when the system theme changes
+    #[argh(switch)]
+    pin_theme: bool,
+
+    /// a comma-separated list of the only languages to play with, like
+    /// "Rust,Go,C,C++"
+    #[argh(option)]
+    languages: Option<String>,
+
+    /// the raw URL of a file to play a single round with, instead of random
+    /// code
+    #[argh(option)]
+    url: Option<String>,
+
+    /// the URL of a Git repository to shallow clone and play the files of,
+    /// instead of random code
+    #[argh(option)]
+    git: Option<String>,
+
+    /// play with the snippets bundled with the game, without network access
+    #[argh(switch)]
+    offline: bool,
+
+    /// don't save fetched code on disk to play again when Github can't be
+    /// reached
+    #[argh(switch)]
+    no_cache: bool,
+
+    /// a file to write the result of the game to as JSON when it ends
+    #[argh(option)]
+    export: Option<String>,
+
+    /// a number that makes the same choices happen in every game, so the same
+    /// languages, options, and reveal order come up
+    #[argh(option)]
+    seed: Option<u64>,
+
+    /// choose your theme, provider, wait time, and token, which is also done
+    /// the first time the game is played
+    #[argh(switch)]
+    setup: bool,
+
+    /// show your accuracy, average points, and streaks for each language,
+    /// instead of playing
+    #[argh(switch)]
+    stats: bool,
+
+    /// a challenge code to play the same game as someone else
+    #[argh(option)]
+    challenge: Option<String>,
+
+    /// login to sign in with Github in the browser instead of passing a token
+    #[argh(positional)]
+    command: Option<String>,
+}
+
+impl Args {
+    /// The provider from `--provider`, falling back to the one chosen with
+    /// `--setup`.
+    #[must_use]
+    pub fn provider(&self) -> Option<&str> {
+        self.provider.as_deref().or(CONFIG.provider.as_deref())
+    }
+
+    /// The wait from `--wait`, falling back to the one chosen with `--setup`.
+    #[must_use]
+    pub fn wait(&self) -> u64 {
+        self.wait.or(CONFIG.wait).unwrap_or(DEFAULT_WAIT)
+    }
+
+    /// Parse the arguments and apply the challenge code, if one was given.
+    /// Malformed codes are treated like any other invalid argument.
+    fn resolve() -> Self {
+        let mut args: Self = argh::from_env();
+
+        if let Some(code) = args.challenge.clone() {
+            match Challenge::decode(&code) {
+                Ok(challenge) => challenge.apply(&mut args),
+                Err(err) => {
+                    eprintln!("{err}");
+                    process::exit(1);
+                }
+            }
+        }
+
+        args
+    }
 }
 
 /// Values to be persisted in a .toml file.
-#[derive(Default, Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Config {
     high_score: u32,
     token: String,
+    /// Extra tokens to use when the main one is ratelimited.
+    #[serde(default)]
+    tokens: Vec<String>,
+    /// The token for the gitlab provider.
+    #[serde(default)]
+    gitlab_token: String,
     theme: Option<ThemeStyle>,
+    /// A bundled theme to use instead of the dark or light one.
+    #[serde(default)]
+    theme_name: Option<String>,
+    /// A .tmTheme file to use instead of the dark or light theme.
+    #[serde(default)]
+    theme_file: Option<PathBuf>,
+    /// The difficulty used last, when `--difficulty` isn't given.
+    #[serde(default)]
+    difficulty: Option<Difficulty>,
+    /// The palette used last, when `--palette` isn't given.
+    #[serde(default)]
+    palette: Option<Palette>,
+    /// The patterns that mark a file from the repos provider as a test.
+    #[serde(default = "Config::default_test_patterns")]
+    test_patterns: Vec<String>,
+    /// The languages to play with when `--languages` isn't given. Every
+    /// language is played with if it's empty.
+    #[serde(default)]
+    languages: Vec<String>,
+    /// How many recently played snippets are remembered so they aren't
+    /// repeated, across sessions.
+    #[serde(default = "Config::default_history_size")]
+    history_size: usize,
+    /// The provider to use when `--provider` isn't given.
+    #[serde(default)]
+    provider: Option<String>,
+    /// The number of ms to wait before revealing code when `--wait` isn't
+    /// given.
+    #[serde(default)]
+    wait: Option<u64>,
+    /// Whether `--setup` was gone through, so it isn't started automatically
+    /// again.
+    #[serde(default)]
+    set_up: bool,
+    /// How many answers were correct out of how many were given, for each
+    /// language. These are moved to their own file by [`Stats::load`].
+    #[serde(default, skip_serializing_if = "LegacyStats::is_empty")]
+    stats: LegacyStats,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            high_score: 0,
+            token: String::new(),
+            tokens: Vec::new(),
+            gitlab_token: String::new(),
+            theme: None,
+            theme_name: None,
+            theme_file: None,
+            difficulty: None,
+            palette: None,
+            test_patterns: Self::default_test_patterns(),
+            languages: Vec::new(),
+            history_size: Self::default_history_size(),
+            provider: None,
+            wait: None,
+            set_up: false,
+            stats: LegacyStats::new(),
+        }
+    }
+}
+
+impl Config {
+    /// The patterns for common test file names and directories.
+    #[must_use]
+    pub fn default_test_patterns() -> Vec<String> {
+        [
+            "_test.",
+            ".test.",
+            ".spec.",
+            "test/",
+            "tests/",
+            "__tests__/",
+            "spec/",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    #[must_use]
+    pub fn default_history_size() -> usize {
+        500
+    }
+
+    /// Load the config file. An invalid config file (e.g. a high score that
+    /// isn't a number) is reported like an invalid argument.
+    fn load() -> Self {
+        confy::load("guess-that-lang").unwrap_or_else(|err| {
+            eprintln!("Invalid config file: {err}");
+            process::exit(1);
+        })
+    }
 }
 
 lazy_static! {
-    pub static ref ARGS: Args = argh::from_env();
-    pub static ref CONFIG: Config = confy::load("guess-that-lang").unwrap();
+    pub static ref ARGS: Args = Args::resolve();
+    pub static ref CONFIG: Config = Config::load();
 }
 
-pub fn main() -> Result<()> {
+pub fn main() {
+    if let Err(err) = play() {
+        eprintln!("{err}");
+        process::exit(err.exit_code());
+    }
+}
+
+/// Play until the user quits or answers incorrectly.
+pub fn play() -> Result<()> {
+    match ARGS.command.as_deref() {
+        Some("login") => return providers::login(),
+        Some(command) => {
+            return Err(GuessError::Config(format!(
+                "Unknown command \"{command}\", valid values: login"
+            )))
+        }
+        None => {}
+    }
+
+    // The config isn't loaded until the setup is done, so that the choices
+    // made in it are used right away. It's only started by itself when no
+    // options are given, since they may already decide how the game is
+    // played, like `--challenge`, or skip playing, like `--stats`.
+    if ARGS.setup || (env::args().len() == 1 && setup::is_first_run()) {
+        setup::run()?;
+
+        if ARGS.setup {
+            return Ok(());
+        }
+    }
+
+    if ARGS.stats {
+        println!("{}", stats::format_table(&Stats::load()));
+        return Ok(());
+    }
+
+    if ARGS.list_providers {
+        println!("{}", providers::list());
+        return Ok(());
+    }
+
     let mut game = Game::new()?;
-    let mut result = game.start_new_round(None)?;
 
-    while let ControlFlow::Continue(_) = result {
+    let mut result = game.start_new_round()?;
+
+    while let ControlFlow::Continue(()) = result {
         result = game.start_next_round()?;
     }
 
+    // The game ended normally, so there's nothing to resume.
+    Game::clear_checkpoint();
+    game.export()?;
+
     Ok(())
 }
@@ -0,0 +1,345 @@
+use std::{
+    cmp::Reverse,
+    collections::hash_map::DefaultHasher,
+    env, fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use ureq::serde_json;
+
+use crate::{
+    error::GuessError,
+    providers::{self, offline::OfflineProvider, CodeData, GithubProvider, PROVIDERS},
+    Result, ARGS,
+};
+
+/// How long cached code is kept before it's evicted.
+pub const CACHE_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// The most bytes of code that are kept, beyond which the oldest code is
+/// evicted.
+pub const MAX_CACHE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Where code is played from when it can't be fetched.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Fallback {
+    /// Code that was fetched in an earlier game.
+    Cache,
+    /// The snippets bundled with the game (see [`OfflineProvider`]).
+    Offline,
+}
+
+/// Where the last code came from if it couldn't be fetched, which is shown as
+/// a notice while playing.
+pub static FALLBACK: Mutex<Option<Fallback>> = Mutex::new(None);
+
+/// Code as it's stored in the cache, one file per snippet.
+#[derive(Serialize, Deserialize)]
+pub struct CachedCode {
+    pub language: String,
+    pub code: String,
+    pub content_type: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// Get the directory code is cached in, which follows the XDG base directory
+/// spec on Linux.
+#[must_use]
+pub fn cache_dir() -> Option<PathBuf> {
+    let home = || env::var_os("HOME").map(PathBuf::from);
+    let base = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            if cfg!(windows) {
+                env::var_os("LOCALAPPDATA").map(PathBuf::from)
+            } else if cfg!(target_os = "macos") {
+                home().map(|home| home.join("Library").join("Caches"))
+            } else {
+                home().map(|home| home.join(".cache"))
+            }
+        })?;
+
+    Some(base.join("guess-that-lang").join("snippets"))
+}
+
+/// Save code in `dir`, named after a hash of it so that it's only stored once.
+/// Failing to cache code shouldn't stop the game, so errors are ignored.
+pub fn store(dir: &Path, data: &CodeData) {
+    let mut hasher = DefaultHasher::new();
+    (&data.language, &data.code).hash(&mut hasher);
+
+    let cached = CachedCode {
+        language: data.language.clone(),
+        code: data.code.clone(),
+        content_type: data.content_type.clone(),
+        source: data.source.clone(),
+    };
+
+    if let Ok(json) = serde_json::to_string(&cached) {
+        let _dir = fs::create_dir_all(dir);
+        let _code = fs::write(dir.join(format!("{:016x}.json", hasher.finish())), json);
+    }
+}
+
+/// Load random cached code from `dir` in one of `languages`, skipping the
+/// files in `used`.
+#[must_use]
+pub fn load(
+    dir: &Path,
+    languages: &[&'static str],
+    used: &[PathBuf],
+    rng: &mut StdRng,
+) -> Option<(PathBuf, CodeData)> {
+    let candidates: Vec<_> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| !used.contains(path))
+        .filter_map(|path| {
+            let cached: CachedCode = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+            languages
+                .contains(&cached.language.as_str())
+                .then_some((path, cached))
+        })
+        .collect();
+
+    let (path, cached) = candidates.choose(rng)?;
+    let data = CodeData {
+        code: cached.code.clone(),
+        language: cached.language.clone(),
+        content_type: cached.content_type.clone(),
+        source: cached.source.clone(),
+    };
+
+    Some((path.clone(), data))
+}
+
+/// Get the cached files to evict out of `entries`, which are their paths,
+/// when they were last modified, and their sizes. Files older than
+/// [`CACHE_TTL`] are evicted, and so are the oldest ones beyond
+/// [`MAX_CACHE_SIZE`].
+#[must_use]
+pub fn evictions(mut entries: Vec<(PathBuf, SystemTime, u64)>, now: SystemTime) -> Vec<PathBuf> {
+    entries.sort_by_key(|(_, modified, _)| Reverse(*modified));
+
+    let mut size = 0;
+    entries
+        .into_iter()
+        .filter_map(|(path, modified, len)| {
+            let age = now.duration_since(modified).unwrap_or_default();
+            if age > CACHE_TTL || size + len > MAX_CACHE_SIZE {
+                Some(path)
+            } else {
+                size += len;
+                None
+            }
+        })
+        .collect()
+}
+
+/// Remove the files in `dir` that are evicted according to [`evictions`].
+pub fn evict(dir: &Path) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let metadata = entry.metadata().ok()?;
+                Some((entry.path(), metadata.modified().ok()?, metadata.len()))
+            })
+            .collect(),
+        Err(_) => return,
+    };
+
+    for path in evictions(entries, SystemTime::now()) {
+        let _file = fs::remove_file(path);
+    }
+}
+
+/// Saves the code fetched by another provider on disk, and plays it again
+/// when Github can't be reached or the ratelimit is exhausted. Each snippet is
+/// only played once per session this way. When there's no cached code left,
+/// or with `--no-cache`, the snippets bundled with the game are played
+/// instead.
+pub struct CachedProvider {
+    provider: Box<dyn GithubProvider>,
+    dir: Option<PathBuf>,
+    used: Vec<PathBuf>,
+    /// Created the first time it's needed.
+    offline: Option<OfflineProvider>,
+}
+
+impl CachedProvider {
+    /// Cache the code fetched by `provider`, evicting old code first.
+    #[must_use]
+    pub fn wrap(provider: Box<dyn GithubProvider>) -> Self {
+        let dir = if ARGS.no_cache { None } else { cache_dir() };
+        if let Some(dir) = &dir {
+            evict(dir);
+        }
+
+        Self {
+            provider,
+            dir,
+            used: Vec::new(),
+            offline: None,
+        }
+    }
+
+    /// Get code in one of `languages` from the cache, or from the bundled
+    /// snippets if there's none, and remember which one it was in
+    /// [`FALLBACK`].
+    pub fn get_fallback(
+        &mut self,
+        languages: &[&'static str],
+        rng: &mut StdRng,
+    ) -> Result<CodeData> {
+        let cached = self
+            .dir
+            .as_ref()
+            .and_then(|dir| load(dir, languages, &self.used, rng));
+
+        let (data, fallback) = if let Some((path, data)) = cached {
+            self.used.push(path);
+            (data, Fallback::Cache)
+        } else {
+            let offline = match &mut self.offline {
+                Some(offline) => offline,
+                None => self.offline.insert(OfflineProvider::new()?),
+            };
+
+            (offline.get_code(languages, rng)?, Fallback::Offline)
+        };
+
+        set_fallback(Some(fallback));
+        Ok(data)
+    }
+}
+
+/// Set where the last code came from, see [`FALLBACK`].
+pub fn set_fallback(fallback: Option<Fallback>) {
+    if let Ok(mut last) = FALLBACK.lock() {
+        *last = fallback;
+    }
+}
+
+/// Get where the last code came from, see [`FALLBACK`].
+#[must_use]
+pub fn fallback() -> Option<Fallback> {
+    FALLBACK.lock().ok().and_then(|fallback| *fallback)
+}
+
+impl GithubProvider for CachedProvider {
+    fn new() -> Result<Self> {
+        Ok(Self::wrap(providers::create(PROVIDERS[0].0)?))
+    }
+
+    fn get_code(&mut self, languages: &[&'static str], rng: &mut StdRng) -> Result<CodeData> {
+        match self.provider.get_code(languages, rng) {
+            Ok(data) => {
+                if let Some(dir) = &self.dir {
+                    store(dir, &data);
+                }
+
+                set_fallback(None);
+                Ok(data)
+            }
+            Err(err @ (GuessError::Network(_) | GuessError::Ratelimited(_))) => {
+                self.get_fallback(languages, rng).map_err(|_| err)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn ratelimit_remaining(&self) -> Option<u32> {
+        self.provider.ratelimit_remaining()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn cache_round_trip() {
+        let dir = env::temp_dir().join(format!("guess-that-lang-cache-{}", std::process::id()));
+        let data = CodeData {
+            code: String::from("fn main() {}\n"),
+            language: String::from("Rust"),
+            content_type: None,
+            source: None,
+        };
+
+        store(&dir, &data);
+        store(&dir, &data);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let (mut used, mut rng) = (Vec::new(), StdRng::seed_from_u64(0));
+        assert!(load(&dir, &["Go"], &used, &mut rng).is_none());
+
+        let (path, cached) = load(&dir, &["Go", "Rust"], &used, &mut rng).unwrap();
+        assert_eq!((cached.code, cached.language), (data.code, data.language));
+
+        used.push(path);
+        assert!(load(&dir, &["Rust"], &used, &mut rng).is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn fall_back_to_bundled_snippets() {
+        struct RatelimitedProvider;
+
+        impl GithubProvider for RatelimitedProvider {
+            fn new() -> Result<Self> {
+                Ok(Self)
+            }
+
+            fn get_code(
+                &mut self,
+                _languages: &[&'static str],
+                _rng: &mut StdRng,
+            ) -> Result<CodeData> {
+                Err(GuessError::Ratelimited(String::from("ratelimited")))
+            }
+        }
+
+        let mut provider = CachedProvider {
+            provider: Box::new(RatelimitedProvider),
+            dir: None,
+            used: Vec::new(),
+            offline: None,
+        };
+
+        let data = provider
+            .get_code(&["Rust"], &mut StdRng::seed_from_u64(0))
+            .unwrap();
+        assert_eq!(data.language, "Rust");
+        assert_eq!(fallback(), Some(Fallback::Offline));
+    }
+
+    #[test]
+    fn cache_evictions() {
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        let entry = |name: &str, age, len| (PathBuf::from(name), now - day * age, len);
+
+        let evicted = evictions(
+            vec![
+                entry("old", 31, 1),
+                entry("new", 0, MAX_CACHE_SIZE - 1),
+                entry("big", 2, 2),
+                entry("small", 3, 1),
+            ],
+            now,
+        );
+
+        assert_eq!(evicted, [PathBuf::from("big"), PathBuf::from("old")]);
+    }
+}
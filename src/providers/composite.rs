@@ -0,0 +1,201 @@
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    rngs::StdRng,
+};
+
+use crate::{
+    error::GuessError,
+    providers::{create, CodeData, GithubProvider, PROVIDERS},
+    Result, ARGS,
+};
+
+/// A function that creates the provider registered under a name.
+pub type NamedConstructor = fn(&str) -> Result<Box<dyn GithubProvider>>;
+
+/// Parse a `--provider` value like `repos,gists` or `repos:70,gists:30` into
+/// the name of each provider and its weight, if it has one. Either every
+/// provider has a weight or none of them do.
+pub fn parse_spec(spec: &str) -> Result<Vec<(&str, Option<u32>)>> {
+    let providers = spec
+        .split(',')
+        .map(|provider| match provider.split_once(':') {
+            Some((name, weight)) => match weight.trim().parse() {
+                Ok(weight) if weight > 0 => Ok((name.trim(), Some(weight))),
+                _ => Err(GuessError::Config(format!(
+                    "Invalid weight \"{weight}\" for provider \"{name}\" (expected a number above 0)"
+                ))),
+            },
+            None => Ok((provider.trim(), None)),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let weighted = providers
+        .iter()
+        .filter(|(_, weight)| weight.is_some())
+        .count();
+    if weighted != 0 && weighted != providers.len() {
+        return Err(GuessError::Config(String::from(
+            "Either every provider in --provider needs a weight, or none of them do",
+        )));
+    }
+
+    Ok(providers)
+}
+
+/// Mixes code from several providers, e.g. with `--provider repos,gists`, by
+/// taking turns between them, or by picking one at random for every snippet
+/// with `--provider repos:70,gists:30`.
+pub struct CompositeProvider {
+    providers: Vec<Box<dyn GithubProvider>>,
+    /// How likely each provider is to be picked, if they were given weights.
+    weights: Option<WeightedIndex<u32>>,
+    /// The index of the provider the next code is taken from when they take
+    /// turns.
+    next: usize,
+}
+
+impl CompositeProvider {
+    /// Take turns between `providers`, starting with the first one.
+    #[must_use]
+    pub fn mix(providers: Vec<Box<dyn GithubProvider>>) -> Self {
+        Self {
+            providers,
+            weights: None,
+            next: 0,
+        }
+    }
+
+    /// Pick one of `providers` at random for every snippet, with each one
+    /// being as likely to be picked as its weight.
+    pub fn weighted(providers: Vec<(Box<dyn GithubProvider>, u32)>) -> Result<Self> {
+        let (providers, weights): (Vec<_>, Vec<_>) = providers.into_iter().unzip();
+        let weights = WeightedIndex::new(weights)
+            .map_err(|err| GuessError::Config(format!("Invalid provider weights: {err}")))?;
+
+        Ok(Self {
+            providers,
+            weights: Some(weights),
+            next: 0,
+        })
+    }
+
+    /// Create the providers in a `--provider` value with `create`, which are
+    /// mixed if there's more than one of them (see [`parse_spec`]).
+    pub fn from_spec(spec: &str, create: NamedConstructor) -> Result<Box<dyn GithubProvider>> {
+        match parse_spec(spec)?.as_slice() {
+            [(name, _)] => create(name),
+            providers => Ok(Box::new(Self::build(providers, create)?)),
+        }
+    }
+
+    /// Create and mix `providers`, with the weights they were given, if any.
+    pub fn build(providers: &[(&str, Option<u32>)], create: NamedConstructor) -> Result<Self> {
+        let created = providers
+            .iter()
+            .map(|(name, _)| create(name))
+            .collect::<Result<Vec<_>>>()?;
+
+        match providers
+            .iter()
+            .map(|(_, weight)| *weight)
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(weights) => Self::weighted(created.into_iter().zip(weights).collect()),
+            None => Ok(Self::mix(created)),
+        }
+    }
+}
+
+impl GithubProvider for CompositeProvider {
+    /// Mix every provider named in `--provider`.
+    fn new() -> Result<Self> {
+        let spec = ARGS.provider().unwrap_or(PROVIDERS[0].0);
+        Self::build(&parse_spec(spec)?, create)
+    }
+
+    /// Get code from the provider whose turn it is, or one picked by weight.
+    /// If it has none, the next attempt is made with another one.
+    fn get_code(&mut self, languages: &[&'static str], rng: &mut StdRng) -> Result<CodeData> {
+        let idx = if let Some(weights) = &self.weights {
+            weights.sample(rng)
+        } else {
+            let idx = self.next;
+            self.next = (self.next + 1) % self.providers.len();
+            idx
+        };
+
+        self.providers[idx].get_code(languages, rng)
+    }
+
+    /// The lowest number of requests left of any of the providers.
+    fn ratelimit_remaining(&self) -> Option<u32> {
+        self.providers
+            .iter()
+            .filter_map(|provider| provider.ratelimit_remaining())
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    struct NamedProvider(&'static str);
+
+    impl GithubProvider for NamedProvider {
+        fn new() -> Result<Self> {
+            Ok(Self(""))
+        }
+
+        fn get_code(&mut self, _languages: &[&'static str], _rng: &mut StdRng) -> Result<CodeData> {
+            Ok(CodeData {
+                code: String::new(),
+                language: self.0.to_string(),
+                content_type: None,
+                source: None,
+            })
+        }
+    }
+
+    #[test]
+    fn providers_take_turns() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut provider = CompositeProvider::mix(vec![
+            Box::new(NamedProvider("repos")),
+            Box::new(NamedProvider("gists")),
+        ]);
+
+        let order: Vec<_> = (0..3)
+            .map(|_| provider.get_code(&[], &mut rng).unwrap().language)
+            .collect();
+        assert_eq!(order, ["repos", "gists", "repos"]);
+    }
+
+    #[test]
+    fn weighted_providers() {
+        assert_eq!(
+            parse_spec("repos:70, gists:30").unwrap(),
+            [("repos", Some(70)), ("gists", Some(30))]
+        );
+        assert_eq!(
+            parse_spec("repos,gists").unwrap(),
+            [("repos", None), ("gists", None)]
+        );
+        assert!(parse_spec("repos:70,gists").is_err());
+        assert!(parse_spec("repos:0,gists:1").is_err());
+        assert!(parse_spec("repos:lots").is_err());
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut provider = CompositeProvider::weighted(vec![
+            (Box::new(NamedProvider("repos")), 1),
+            (Box::new(NamedProvider("gists")), 1000),
+        ])
+        .unwrap();
+
+        let gists = (0..100)
+            .filter(|_| provider.get_code(&[], &mut rng).unwrap().language == "gists")
+            .count();
+        assert!(gists > 90, "{gists}");
+    }
+}
@@ -1,17 +1,27 @@
 use std::{collections::BTreeMap, result};
 
-use rand::{seq::SliceRandom, thread_rng, Rng};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 use serde::Deserialize;
 use ureq::Agent;
 
 use crate::{
-    game::LANGUAGES,
-    providers::{AuthenticationExt, CodeData, GithubProvider, GITHUB_BASE_URL},
+    error::GuessError,
+    game::Game,
+    providers::{
+        history,
+        notebooks::{self, NOTEBOOK_LANGUAGE},
+        AuthenticationExt, CodeData, GithubProvider, Tokens, GITHUB_BASE_URL,
+    },
     Result,
 };
 
+/// The maximum number of pages of gists to fetch in [`GistProvider::get_code`]
+/// when looking for a gist in one of the requested languages.
+pub const MAX_PAGE_ATTEMPTS: usize = 3;
+
 #[derive(Deserialize)]
 pub struct Gist {
+    pub html_url: String,
     pub files: BTreeMap<String, GistFile>,
 }
 
@@ -24,53 +34,68 @@ pub struct GistFile {
 pub struct GistData {
     pub url: String,
     pub language: String,
+    /// The gist's page on Github.
+    pub html_url: String,
 }
 
 impl TryFrom<Gist> for GistData {
     type Error = ();
 
     /// Create a new [`GistData`] struct from a [`Gist`]. This will return [`None`]
-    /// if none of the gist files use one of the supported languages.
+    /// if none of the gist files use one of the selected languages or are
+    /// notebooks, which are only used if there's nothing else.
     fn try_from(gist: Gist) -> result::Result<Self, Self::Error> {
-        let file = gist
+        let languages = Game::selected_languages();
+        let (notebooks, files): (Vec<_>, Vec<_>) = gist
             .files
             .into_values()
+            .filter(|file| file.language.is_some())
+            .partition(|file| file.language.as_deref() == Some(NOTEBOOK_LANGUAGE));
+
+        let file = files
+            .into_iter()
             .find(|file| {
                 file.language
                     .as_ref()
-                    .map_or(false, |language| LANGUAGES.contains(&language.as_str()))
+                    .map_or(false, |language| languages.contains(&language.as_str()))
             })
+            .or_else(|| notebooks.into_iter().next())
             .ok_or(())?;
 
         Ok(Self {
-            url: file.raw_url.to_string(),
+            url: file.raw_url.clone(),
             language: file.language.unwrap(),
+            html_url: gist.html_url,
         })
     }
 }
 
 pub struct GistProvider {
     agent: Agent,
-    token: Option<String>,
+    tokens: Tokens,
     cache: Vec<GistData>,
 }
 
 impl GistProvider {
     /// Get a vec of random valid gists on Github. This is used with the assumption
     /// that at least one valid gist will be found.
-    pub fn get_gists(&self) -> Result<Vec<GistData>> {
+    pub fn get_gists(&self, rng: &mut StdRng) -> Result<Vec<GistData>> {
+        let page = rng.gen_range(0..=100).to_string();
         let mut gists: Vec<_> = self
-            .agent
-            .get(&format!("{GITHUB_BASE_URL}/gists/public"))
-            .query("page", &thread_rng().gen_range(0..=100).to_string())
-            .with_authentication(self.token.as_ref())
-            .call()?
+            .tokens
+            .call(|token| {
+                self.agent
+                    .get(&format!("{GITHUB_BASE_URL}/gists/public"))
+                    .query("page", &page)
+                    .with_authentication(token)
+            })?
             .into_json::<Vec<Gist>>()?
             .into_iter()
             .filter_map(|gist| GistData::try_from(gist).ok())
+            .filter(|gist| !history::is_seen(&gist.url))
             .collect();
 
-        gists.shuffle(&mut thread_rng());
+        gists.shuffle(rng);
 
         Ok(gists)
     }
@@ -79,19 +104,19 @@ impl GistProvider {
     /// languages, push them to a vec along with the correct language, and
     /// shuffle the vec.
     #[must_use]
-    pub fn get_options(correct_language: String) -> Vec<String> {
+    pub fn get_options(correct_language: String, rng: &mut StdRng) -> Vec<String> {
         let mut options = Vec::<String>::with_capacity(4);
         options.push(correct_language);
 
-        let mut thread_rng = thread_rng();
+        let languages = Game::selected_languages();
         while options.len() < 4 {
-            let random_language = (*LANGUAGES.choose(&mut thread_rng).unwrap()).to_string();
+            let random_language = (*languages.choose(rng).unwrap()).to_string();
             if !options.contains(&random_language) {
                 options.push(random_language);
             }
         }
 
-        options.shuffle(&mut thread_rng);
+        options.shuffle(rng);
         options
     }
 }
@@ -99,30 +124,71 @@ impl GistProvider {
 impl GithubProvider for GistProvider {
     fn new() -> Result<Self> {
         let agent = Self::get_agent();
-        let token = Self::apply_token(&agent)?;
+        let tokens = Self::apply_tokens(&agent)?;
 
         Ok(Self {
             agent,
-            token,
+            tokens,
             cache: Vec::with_capacity(0),
         })
     }
 
-    fn get_code(&mut self) -> Result<CodeData> {
-        if self.cache.is_empty() {
-            self.cache = self.get_gists()?;
+    fn ratelimit_remaining(&self) -> Option<u32> {
+        self.tokens.remaining()
+    }
+
+    fn get_code(&mut self, languages: &[&'static str], rng: &mut StdRng) -> Result<CodeData> {
+        // A notebook's language is only known once it's fetched (see
+        // [`notebooks::extract`]).
+        let is_wanted = |gist: &GistData| {
+            gist.language == NOTEBOOK_LANGUAGE || languages.contains(&gist.language.as_str())
         };
 
-        let gist = self.cache.pop().unwrap();
+        loop {
+            // Gists can't be searched by language, so a few pages are tried
+            // before giving up. The last page is kept, in case the game asks
+            // for other languages next.
+            for _ in 0..MAX_PAGE_ATTEMPTS {
+                if self.cache.iter().any(is_wanted) {
+                    break;
+                }
 
-        Ok(CodeData {
-            code: self
-                .agent
-                .get(&gist.url)
-                .with_authentication(self.token.as_ref())
-                .call()?
-                .into_string()?,
-            language: gist.language.clone(),
-        })
+                self.cache = self.get_gists(rng)?;
+            }
+
+            let idx = self.cache.iter().position(is_wanted).ok_or_else(|| {
+                GuessError::NoSnippet(format!("No gists in {} were found", languages.join(", ")))
+            })?;
+            let gist = self.cache.swap_remove(idx);
+
+            history::record(&gist.url);
+
+            let response = self
+                .tokens
+                .call(|token| self.agent.get(&gist.url).with_authentication(token))?;
+            let content_type = Some(response.content_type().to_string());
+            let code = response.into_string()?;
+
+            if gist.language != NOTEBOOK_LANGUAGE {
+                return Ok(CodeData {
+                    code,
+                    language: gist.language,
+                    content_type,
+                    source: Some(gist.html_url),
+                });
+            }
+
+            // A notebook's content is JSON, so its code cells are used instead,
+            // in the language of its kernel. Notebooks that can't be used, or
+            // aren't in one of `languages`, are skipped.
+            if let Some((language, code)) = notebooks::extract(&code, languages) {
+                return Ok(CodeData {
+                    code,
+                    language,
+                    content_type: None,
+                    source: Some(gist.html_url),
+                });
+            }
+        }
     }
 }
@@ -0,0 +1,114 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+};
+
+use rand::rngs::StdRng;
+
+use crate::{
+    error::GuessError,
+    providers::{local::LocalProvider, CodeData, GithubProvider},
+    Result, ARGS,
+};
+
+/// A directory a repository was cloned into, which is removed when it's
+/// dropped.
+pub struct CloneDirectory(pub PathBuf);
+
+impl Drop for CloneDirectory {
+    fn drop(&mut self) {
+        let _removed = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Shallow clone the repository at `url` into `directory` with the `git`
+/// command, without prompting for credentials.
+pub fn shallow_clone(url: &str, directory: &Path) -> Result<()> {
+    let output = Command::new("git")
+        .args(["clone", "--depth", "1", "--quiet", "--", url])
+        .arg(directory)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| GuessError::Config(format!("Could not run git to clone {url}: {err}")))?;
+
+    if !output.status.success() {
+        return Err(GuessError::Config(format!(
+            "Could not clone {url}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Plays the files of the repository given with `--git`, which is cloned into
+/// a temporary directory that's removed when the game ends. No token is
+/// needed, and Github's ratelimit doesn't apply.
+pub struct GitProvider {
+    local: LocalProvider,
+    _clone: CloneDirectory,
+}
+
+impl GithubProvider for GitProvider {
+    fn new() -> Result<Self> {
+        let url = ARGS
+            .git
+            .as_deref()
+            .ok_or_else(|| GuessError::Config(String::from("No repository was given (--git)")))?;
+
+        let clone =
+            CloneDirectory(env::temp_dir().join(format!("guess-that-lang-git-{}", process::id())));
+        let _leftover = fs::remove_dir_all(&clone.0);
+        shallow_clone(url, &clone.0)?;
+
+        Ok(Self {
+            local: LocalProvider::from_paths(&[clone.0.display().to_string()])?,
+            _clone: clone,
+        })
+    }
+
+    fn get_code(&mut self, languages: &[&'static str], rng: &mut StdRng) -> Result<CodeData> {
+        self.local.get_code(languages, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clone_repository() {
+        let root = env::temp_dir().join(format!("guess-that-lang-clone-{}", process::id()));
+        let (origin, clone) = (root.join("origin"), root.join("clone"));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&origin).unwrap();
+        fs::write(origin.join("main.rs"), "fn main() {}").unwrap();
+
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(&origin)
+                .output()
+                .unwrap()
+        };
+        git(&["init", "--quiet"]);
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "Initial commit"]);
+
+        let url = format!("file://{}", origin.display());
+        let cloned = shallow_clone(&url, &clone).map(|()| clone.join("main.rs").exists());
+        let missing = shallow_clone(&format!("{url}-missing"), &root.join("missing"));
+
+        // The clone is removed along with its directory.
+        drop(CloneDirectory(clone.clone()));
+        let is_removed = !clone.exists();
+        let _ = fs::remove_dir_all(&root);
+
+        assert!(cloned.unwrap());
+        assert!(matches!(missing, Err(GuessError::Config(_))));
+        assert!(is_removed);
+    }
+}
@@ -0,0 +1,246 @@
+use serde::Deserialize;
+use ureq::serde_json::{self, json};
+
+pub const GRAPHQL_URL: &str = "https://api.github.com/graphql";
+
+/// Gets the files in the top two levels of a repository's default branch,
+/// with their contents, in a single request. With the REST API, this takes a
+/// code search, a request for each file's size, and a download.
+pub const TREE_QUERY: &str = "
+query($owner: String!, $name: String!) {
+  repository(owner: $owner, name: $name) {
+    defaultBranchRef {
+      target {
+        ... on Commit {
+          tree {
+            entries {
+              path
+              object {
+                ...File
+                ... on Tree { entries { path object { ...File } } }
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+fragment File on Blob { byteSize isBinary text }
+";
+
+/// The file extensions (or whole file names) of each of
+/// [`crate::game::LANGUAGES`], which the GraphQL API doesn't detect like code
+/// search does.
+pub const LANGUAGE_EXTENSIONS: [(&str, &[&str]); 25] = [
+    ("Assembly", &[".asm", ".s", ".nasm"]),
+    ("Shell", &[".sh", ".bash", ".zsh"]),
+    ("C", &[".c", ".h"]),
+    ("C#", &[".cs"]),
+    ("C++", &[".cpp", ".cc", ".cxx", ".hpp", ".hh"]),
+    ("CSS", &[".css"]),
+    ("Dart", &[".dart"]),
+    ("Dockerfile", &["Dockerfile", ".dockerfile"]),
+    ("Go", &[".go"]),
+    ("Groovy", &[".groovy", ".gradle"]),
+    ("HTML", &[".html", ".htm"]),
+    ("Java", &[".java"]),
+    ("JavaScript", &[".js", ".mjs", ".cjs", ".jsx"]),
+    ("Kotlin", &[".kt", ".kts"]),
+    ("Lua", &[".lua"]),
+    ("MATLAB", &[".m"]),
+    ("PHP", &[".php"]),
+    ("PowerShell", &[".ps1", ".psm1"]),
+    ("Python", &[".py"]),
+    ("R", &[".r", ".R"]),
+    ("Ruby", &[".rb"]),
+    ("Rust", &[".rs"]),
+    ("SQL", &[".sql"]),
+    ("Swift", &[".swift"]),
+    ("TypeScript", &[".ts", ".tsx"]),
+];
+
+#[derive(Deserialize)]
+pub struct Response<T> {
+    pub data: Option<T>,
+}
+
+#[derive(Deserialize)]
+pub struct TreeData {
+    pub repository: Option<Repository>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Repository {
+    pub default_branch_ref: Option<BranchRef>,
+}
+
+#[derive(Deserialize)]
+pub struct BranchRef {
+    pub target: Commit,
+}
+
+#[derive(Deserialize)]
+pub struct Commit {
+    pub tree: Option<Tree>,
+}
+
+#[derive(Deserialize)]
+pub struct Tree {
+    pub entries: Vec<TreeEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct TreeEntry {
+    pub path: String,
+    pub object: Option<TreeObject>,
+}
+
+/// Either a file or a directory, depending on which fields are present.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeObject {
+    pub byte_size: Option<u64>,
+    #[serde(default)]
+    pub is_binary: bool,
+    pub text: Option<String>,
+    pub entries: Option<Vec<TreeEntry>>,
+}
+
+/// A text file from [`TREE_QUERY`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct File {
+    pub path: String,
+    pub size: u64,
+    pub text: String,
+}
+
+/// Create the request body for [`TREE_QUERY`] on the repository `name` (e.g.
+/// `owner/repo`).
+#[must_use]
+pub fn tree_request(name: &str) -> Option<serde_json::Value> {
+    let (owner, name) = name.split_once('/')?;
+
+    Some(json!({
+        "query": TREE_QUERY,
+        "variables": { "owner": owner, "name": name },
+    }))
+}
+
+/// Get every text file from a [`TREE_QUERY`] response.
+#[must_use]
+pub fn get_files(response: Response<TreeData>) -> Vec<File> {
+    let entries = response
+        .data
+        .and_then(|data| data.repository)
+        .and_then(|repository| repository.default_branch_ref)
+        .and_then(|branch| branch.target.tree)
+        .map_or_else(Vec::new, |tree| tree.entries);
+
+    let mut files = Vec::new();
+    collect_files(entries, &mut files);
+
+    files
+}
+
+fn collect_files(entries: Vec<TreeEntry>, files: &mut Vec<File>) {
+    for entry in entries {
+        let object = match entry.object {
+            Some(object) => object,
+            None => continue,
+        };
+
+        if let Some(entries) = object.entries {
+            collect_files(entries, files);
+        } else if let (Some(size), Some(text), false) =
+            (object.byte_size, object.text, object.is_binary)
+        {
+            files.push(File {
+                path: entry.path,
+                size,
+                text,
+            });
+        }
+    }
+}
+
+/// Whether the file at `path` is written in `language`, going by its
+/// extension.
+#[must_use]
+pub fn is_language(path: &str, language: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+
+    LANGUAGE_EXTENSIONS
+        .iter()
+        .find(|(other, _)| *other == language)
+        .map_or(false, |(_, extensions)| {
+            extensions.iter().any(|extension| name.ends_with(extension))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::LANGUAGES;
+
+    #[test]
+    fn every_language_has_extensions() {
+        for language in LANGUAGES {
+            assert!(
+                LANGUAGE_EXTENSIONS
+                    .iter()
+                    .any(|(other, _)| *other == language),
+                "{language} has no extensions"
+            );
+        }
+    }
+
+    #[test]
+    fn tree_files() {
+        let response = serde_json::from_str(
+            r#"{
+                "data": {
+                    "repository": {
+                        "defaultBranchRef": {
+                            "target": {
+                                "tree": {
+                                    "entries": [
+                                        { "path": "README.md", "object": { "byteSize": 5, "isBinary": false, "text": "Hi" } },
+                                        { "path": "logo.png", "object": { "byteSize": 900, "isBinary": true, "text": null } },
+                                        {
+                                            "path": "src",
+                                            "object": {
+                                                "entries": [
+                                                    { "path": "src/main.rs", "object": { "byteSize": 12, "isBinary": false, "text": "fn main() {}" } },
+                                                    { "path": "src/lib", "object": {} }
+                                                ]
+                                            }
+                                        }
+                                    ]
+                                }
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let files = get_files(response);
+        assert_eq!(
+            files.iter().map(|file| &file.path[..]).collect::<Vec<_>>(),
+            ["README.md", "src/main.rs"]
+        );
+        assert_eq!(files[1].size, 12);
+        assert_eq!(files[1].text, "fn main() {}");
+
+        assert!(get_files(serde_json::from_str(r#"{ "data": null }"#).unwrap()).is_empty());
+        assert!(tree_request("no-owner").is_none());
+
+        assert!(is_language("src/main.rs", "Rust"));
+        assert!(is_language("docker/Dockerfile", "Dockerfile"));
+        assert!(!is_language("src/main.rs", "R"));
+    }
+}
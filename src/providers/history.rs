@@ -0,0 +1,92 @@
+use std::{collections::VecDeque, sync::Mutex};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::{ARGS, CONFIG};
+
+/// The name of the file that [`History`] is stored in.
+pub const HISTORY_NAME: &str = "guess-that-lang-history";
+
+lazy_static! {
+    pub static ref HISTORY: Mutex<History> = Mutex::new(History::load());
+}
+
+/// The URLs of the most recently played snippets across sessions, so that
+/// providers can avoid repeating them. At most
+/// [`crate::Config::history_size`] are kept.
+#[derive(Serialize, Deserialize, Default)]
+pub struct History {
+    pub urls: VecDeque<String>,
+}
+
+impl History {
+    /// Load the history, or clear it with `--forget`. Failing to load it
+    /// shouldn't stop the game, so the history is empty in that case.
+    #[must_use]
+    pub fn load() -> Self {
+        if ARGS.forget {
+            let history = Self::default();
+            history.save();
+            return history;
+        }
+
+        confy::load(HISTORY_NAME).unwrap_or_default()
+    }
+
+    /// Save the history, ignoring errors like [`crate::game::Game::save_checkpoint`].
+    pub fn save(&self) {
+        let _history = confy::store(HISTORY_NAME, self);
+    }
+
+    #[must_use]
+    pub fn contains(&self, url: &str) -> bool {
+        self.urls.iter().any(|seen| seen == url)
+    }
+
+    /// Add a URL, forgetting the oldest ones beyond `size`.
+    pub fn add(&mut self, url: &str, size: usize) {
+        self.urls.retain(|seen| seen != url);
+        self.urls.push_back(url.to_string());
+
+        while self.urls.len() > size {
+            self.urls.pop_front();
+        }
+    }
+}
+
+/// Whether a snippet was played recently, in this session or an earlier one.
+#[must_use]
+pub fn is_seen(url: &str) -> bool {
+    HISTORY
+        .lock()
+        .map_or(false, |history| history.contains(url))
+}
+
+/// Remember that a snippet was played and save the history.
+pub fn record(url: &str) {
+    if let Ok(mut history) = HISTORY.lock() {
+        history.add(url, CONFIG.history_size);
+        history.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capped_history() {
+        let mut history = History::default();
+        for url in ["a", "b", "c", "b"] {
+            history.add(url, 2);
+        }
+
+        assert_eq!(history.urls, ["c", "b"]);
+        assert!(history.contains("b"));
+        assert!(!history.contains("a"));
+
+        history.add("d", 0);
+        assert!(history.urls.is_empty());
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom};
+use syntect::parsing::SyntaxSet;
+
+use crate::{
+    error::GuessError,
+    game::LANGUAGES,
+    providers::{CodeData, GithubProvider},
+    terminal::Terminal,
+    Result, ARGS,
+};
+
+/// The error shown when the local provider is used without a directory.
+pub const REQUIRES_PATH: &str =
+    "The local provider requires a directory to get code from (--path).";
+
+/// The characters that make a `--path` a glob pattern instead of a path.
+pub const GLOB_CHARS: [char; 3] = ['*', '?', '['];
+
+/// Gets code from files in directories on this machine, or files matching
+/// glob patterns, so the game can be played offline or on your own code.
+pub struct LocalProvider {
+    /// Every file found written in one of [`LANGUAGES`], with its language.
+    files: Vec<(PathBuf, &'static str)>,
+}
+
+/// Resolve `path` relative to the current directory, so errors show where
+/// the game actually looked.
+pub fn get_absolute_path(path: &str) -> Result<PathBuf> {
+    fs::canonicalize(path)
+        .map_err(|err| GuessError::Config(format!("Could not find the directory {path}: {err}")))
+}
+
+/// Get the language of the file at `path` from its extension (or its name,
+/// for files like `Dockerfile`), if it's one of [`LANGUAGES`].
+#[must_use]
+pub fn get_language(syntaxes: &SyntaxSet, path: &Path) -> Option<&'static str> {
+    let extension = path.extension().or_else(|| path.file_name())?.to_str()?;
+    let syntax = syntaxes.find_syntax_by_extension(extension)?;
+
+    LANGUAGES
+        .iter()
+        .find(|language| Terminal::syntax_name(language) == syntax.name)
+        .copied()
+}
+
+/// Find every file in `paths` written in one of [`LANGUAGES`]. Each path can
+/// be a directory, which is searched recursively, a single file, or a glob
+/// pattern like `src/**/*.rs`. Files found more than once are only kept once,
+/// and files and directories that can't be read are skipped.
+pub fn collect_files(
+    syntaxes: &SyntaxSet,
+    paths: &[String],
+) -> Result<Vec<(PathBuf, &'static str)>> {
+    let mut files = Vec::new();
+    let add = |path: PathBuf, files: &mut Vec<_>| {
+        if path.is_dir() {
+            let _unreadable = find_files(syntaxes, &path, files);
+        } else if let Some(language) = get_language(syntaxes, &path) {
+            files.push((path, language));
+        }
+    };
+
+    for path in paths {
+        if path.contains(GLOB_CHARS) {
+            let matches = glob::glob(path).map_err(|err| {
+                GuessError::Config(format!("Invalid glob pattern \"{path}\": {err}"))
+            })?;
+
+            for path in matches.flatten() {
+                add(path, &mut files);
+            }
+        } else {
+            add(get_absolute_path(path)?, &mut files);
+        }
+    }
+
+    // The same file can be found through different paths, e.g. a glob and a
+    // directory it's in.
+    let mut files: Vec<_> = files
+        .into_iter()
+        .filter_map(|(path, language)| Some((fs::canonicalize(path).ok()?, language)))
+        .collect();
+    files.sort_unstable();
+    files.dedup();
+
+    Ok(files)
+}
+
+/// Recursively find every file in `directory` written in one of
+/// [`LANGUAGES`]. Hidden files and directories (like `.git`) are skipped, as
+/// are subdirectories that can't be read.
+pub fn find_files(
+    syntaxes: &SyntaxSet,
+    directory: &Path,
+    files: &mut Vec<(PathBuf, &'static str)>,
+) -> Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+
+        if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map_or(true, |name| name.starts_with('.'))
+        {
+            continue;
+        }
+
+        if path.is_dir() {
+            let _unreadable = find_files(syntaxes, &path, files);
+        } else if let Some(language) = get_language(syntaxes, &path) {
+            files.push((path, language));
+        }
+    }
+
+    Ok(())
+}
+
+impl LocalProvider {
+    /// Play the files in `paths` (see [`collect_files`]).
+    pub fn from_paths(paths: &[String]) -> Result<Self> {
+        let files = collect_files(&Terminal::load_syntaxes()?, paths)?;
+
+        if files.is_empty() {
+            return Err(GuessError::NoSnippet(format!(
+                "No files in {} are written in a supported language",
+                paths.join(", ")
+            )));
+        }
+
+        Ok(Self { files })
+    }
+}
+
+impl GithubProvider for LocalProvider {
+    fn new() -> Result<Self> {
+        if ARGS.path.is_empty() {
+            return Err(GuessError::Config(String::from(REQUIRES_PATH)));
+        }
+
+        Self::from_paths(&ARGS.path)
+    }
+
+    fn get_code(&mut self, languages: &[&'static str], rng: &mut StdRng) -> Result<CodeData> {
+        loop {
+            let candidates: Vec<_> = self
+                .files
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, language))| languages.contains(language))
+                .map(|(idx, _)| idx)
+                .collect();
+
+            let idx = *candidates.choose(rng).ok_or_else(|| {
+                GuessError::NoSnippet(String::from("No local files in the chosen languages"))
+            })?;
+
+            // Files that can't be read as text (e.g. binaries with a source
+            // extension) are forgotten rather than ending the game.
+            match fs::read_to_string(&self.files[idx].0) {
+                Ok(code) => {
+                    return Ok(CodeData {
+                        code,
+                        language: self.files[idx].1.to_string(),
+                        content_type: None,
+                        source: Some(self.files[idx].0.display().to_string()),
+                    })
+                }
+                Err(_) => {
+                    self.files.swap_remove(idx);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn local_files() {
+        let directory = env::temp_dir().join(format!("guess-that-lang-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        for (path, code) in [
+            ("main.rs", "fn main() {}"),
+            ("notes.txt", "Not code"),
+            ("scripts/build.py", "print('hi')"),
+            (".git/hooks/pre-commit.sh", "exit 0"),
+        ] {
+            let path = directory.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, code).unwrap();
+        }
+
+        let mut files = Vec::new();
+        let result = find_files(&Terminal::load_syntaxes().unwrap(), &directory, &mut files);
+        let _ = fs::remove_dir_all(&directory);
+        result.unwrap();
+
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                (directory.join("main.rs"), "Rust"),
+                (directory.join("scripts/build.py"), "Python")
+            ]
+        );
+    }
+
+    #[test]
+    fn local_paths_and_globs() {
+        let directory =
+            env::temp_dir().join(format!("guess-that-lang-globs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        for path in ["a/main.rs", "a/lib.rs", "b/app.py", "b/app.go"] {
+            let path = directory.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "code").unwrap();
+        }
+
+        let syntaxes = Terminal::load_syntaxes().unwrap();
+        let path = |path: &str| directory.join(path).display().to_string();
+        let result = collect_files(&syntaxes, &[path("a"), path("*/*.rs"), path("b/*.py")]);
+        let invalid = collect_files(&syntaxes, &[path("[")]);
+        let directory = fs::canonicalize(&directory).unwrap();
+        let _ = fs::remove_dir_all(&directory);
+
+        assert_eq!(
+            result.unwrap(),
+            [
+                (directory.join("a/lib.rs"), "Rust"),
+                (directory.join("a/main.rs"), "Rust"),
+                (directory.join("b/app.py"), "Python"),
+            ]
+        );
+        assert!(matches!(invalid, Err(GuessError::Config(_))));
+    }
+}
@@ -1,25 +1,205 @@
+use std::{
+    env,
+    sync::{
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
 use lazy_static::lazy_static;
+use rand::rngs::StdRng;
 use regex::{Regex, RegexBuilder};
-use ureq::{Agent, AgentBuilder, Request, Response};
+use ureq::{serde_json, Agent, AgentBuilder, Request, Response};
 
-use crate::{Config, Result, ARGS, CONFIG};
+use crate::{
+    error::GuessError,
+    providers::{
+        gists::GistProvider, gitlab::GitlabProvider, local::LocalProvider,
+        repos::RepositoryProvider, sourcegraph::SourcegraphProvider,
+    },
+    Config, Result, ARGS, CONFIG,
+};
 
+pub mod cache;
+pub mod composite;
 pub mod gists;
+pub mod git;
+pub mod gitlab;
+pub mod graphql;
+pub mod history;
+pub mod local;
+pub mod notebooks;
+pub mod offline;
+pub mod preload;
 pub mod repos;
+pub mod sourcegraph;
+pub mod url;
 
 pub const GITHUB_BASE_URL: &str = "https://api.github.com";
 
+/// Where a device code is requested for `guess-that-lang login`.
+pub const DEVICE_CODE_URL: &str = "https://github.com/login/device/code";
+
+/// Where the token is polled for once the device code has been entered.
+pub const ACCESS_TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+
+/// The client ID of the OAuth app `guess-that-lang login` signs in with, which
+/// is set when building the game (the release workflow passes it from the
+/// repository's `GUESS_THAT_LANG_CLIENT_ID` variable). Device flow has to be
+/// enabled for the app.
+pub const OAUTH_CLIENT_ID: Option<&str> = option_env!("GUESS_THAT_LANG_CLIENT_ID");
+
+/// A function that creates a provider.
+pub type ProviderConstructor = fn() -> Result<Box<dyn GithubProvider>>;
+
+/// The providers that can be chosen with `--provider`, by name. The first one
+/// is used by default.
+pub const PROVIDERS: [(&str, ProviderConstructor); 6] = [
+    ("repos", || Ok(Box::new(RepositoryProvider::new()?))),
+    ("gists", || Ok(Box::new(GistProvider::new()?))),
+    ("trending", || Ok(Box::new(RepositoryProvider::trending()?))),
+    ("local", || Ok(Box::new(LocalProvider::new()?))),
+    ("gitlab", || Ok(Box::new(GitlabProvider::new()?))),
+    ("sourcegraph", || Ok(Box::new(SourcegraphProvider::new()?))),
+];
+
+/// The environment variables a token is read from when there isn't one in the
+/// arguments or config, in order of precedence.
+pub const TOKEN_ENV_VARS: [&str; 2] = ["GITHUB_TOKEN", "GH_TOKEN"];
+
+/// How many times a request is retried after waiting for the ratelimit to
+/// reset, once every token is ratelimited.
+pub const RATELIMIT_RETRIES: u32 = 2;
+
+/// The longest time to wait for the ratelimit to reset before retrying.
+pub const MAX_RATELIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// The number of Github requests made this session, shown when the game ends.
+pub static API_CALLS: AtomicU32 = AtomicU32::new(0);
+
+/// The `x-ratelimit-remaining` header of the last Github response, shown
+/// below the options. This is [`u32::MAX`] until a response has it.
+pub static RATELIMIT_REMAINING: AtomicU32 = AtomicU32::new(u32::MAX);
+
 lazy_static! {
-    static ref TOKEN_REGEX: Regex = RegexBuilder::new(r"[\da-f]{40}|ghp_\w{36,251}")
+    static ref TOKEN_REGEX: Regex = RegexBuilder::new(r"[\da-f]{40}|gh[po]_\w{36,251}")
         // This is an expensive regex, so the size limit needs to be increased.
         .size_limit(1 << 25)
         .build()
         .unwrap();
 }
 
+/// List the providers in [`PROVIDERS`] for `--list-providers`, one per line.
+#[must_use]
+pub fn list() -> String {
+    PROVIDERS
+        .iter()
+        .enumerate()
+        .map(|(idx, (name, _))| {
+            if idx == 0 {
+                format!("{name} (default)")
+            } else {
+                (*name).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Create the provider registered as `name` in [`PROVIDERS`].
+pub fn create(name: &str) -> Result<Box<dyn GithubProvider>> {
+    match PROVIDERS.iter().find(|(provider, _)| *provider == name) {
+        Some((_, constructor)) => constructor(),
+        None => Err(GuessError::Config(format!(
+            "Unknown provider \"{name}\", valid values: {}",
+            PROVIDERS
+                .iter()
+                .map(|(provider, _)| *provider)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+/// Format the date `days` days before today as YYYY-MM-DD (UTC), for use in
+/// search qualifiers.
+#[must_use]
+pub fn days_ago(days: u32) -> String {
+    let today = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() / 86_400);
+
+    format_date(today.saturating_sub(u64::from(days)))
+}
+
+/// Parse a human duration like `30d`, `2w`, `6m`, or `1y` into a number of
+/// days.
+pub fn parse_days(duration: &str) -> Result<u32> {
+    let invalid = || {
+        GuessError::Config(format!(
+            "Invalid duration \"{duration}\" (expected a number followed by d, w, m, or y, like 30d)"
+        ))
+    };
+
+    let duration = duration.trim();
+    let unit = duration.chars().last().ok_or_else(invalid)?;
+    let amount: u32 = duration[..duration.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+
+    let multiplier = match unit.to_ascii_lowercase() {
+        'd' => 1,
+        'w' => 7,
+        'm' => 30,
+        'y' => 365,
+        _ => return Err(invalid()),
+    };
+
+    amount.checked_mul(multiplier).ok_or_else(invalid)
+}
+
+/// Format a number of days since the unix epoch as YYYY-MM-DD.
+#[must_use]
+pub fn format_date(days_since_epoch: u64) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days_since_epoch + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
 pub struct CodeData {
     pub code: String,
     pub language: String,
+    /// The `Content-Type` the code was served with, used to pick a syntax when
+    /// `language` doesn't match one.
+    pub content_type: Option<String>,
+    /// Where the code can be seen in full, which is linked to when reviewing
+    /// a round with `--review`.
+    pub source: Option<String>,
+}
+
+impl CodeData {
+    /// Whether there's no code at all, like in an empty file, in which case
+    /// there's no point in parsing it.
+    #[must_use]
+    pub fn is_blank(&self) -> bool {
+        self.code.trim().is_empty()
+    }
 }
 
 pub trait GithubProvider: Send {
@@ -27,7 +207,27 @@ pub trait GithubProvider: Send {
     where
         Self: Sized;
 
-    fn get_code(&mut self) -> Result<CodeData>;
+    /// Get code written in one of `languages`, making random choices (like
+    /// which page to look at) with the game's `rng`.
+    fn get_code(&mut self, languages: &[&'static str], rng: &mut StdRng) -> Result<CodeData>;
+
+    /// Get two snippets at once for a bonus round, the first written in one
+    /// of `first` and the second in one of `second`. The provider is locked
+    /// while both are fetched, so no other code is fetched in between.
+    fn get_code_pair(
+        &mut self,
+        first: &[&'static str],
+        second: &[&'static str],
+        rng: &mut StdRng,
+    ) -> Result<(CodeData, CodeData)> {
+        Ok((self.get_code(first, rng)?, self.get_code(second, rng)?))
+    }
+
+    /// The number of requests that can be made before being ratelimited,
+    /// according to the last response, if it's known.
+    fn ratelimit_remaining(&self) -> Option<u32> {
+        None
+    }
 
     #[must_use]
     fn get_agent() -> Agent
@@ -41,8 +241,9 @@ pub trait GithubProvider: Send {
         AgentBuilder::new().user_agent(&user_agent).build()
     }
 
-    /// If a token is found from arguments or the config: validate it and return
-    /// it. If it wasn't found from the config, store it in the config.
+    /// If a token is found from arguments, the config, or [`TOKEN_ENV_VARS`]
+    /// (in that order): validate it and return it. Tokens from arguments are
+    /// stored in the config, but tokens from the environment aren't.
     fn apply_token(agent: &Agent) -> Result<Option<String>>
     where
         Self: Sized,
@@ -51,7 +252,9 @@ pub trait GithubProvider: Send {
             Self::test_token_structure(token)?;
 
             if Self::validate_token(agent, token).is_err() {
-                return Err("Invalid personal access token".into());
+                return Err(GuessError::Auth(String::from(
+                    "Invalid personal access token",
+                )));
             }
 
             confy::store(
@@ -62,7 +265,7 @@ pub trait GithubProvider: Send {
                 },
             )?;
 
-            return Ok(Some(token.to_string()));
+            return Ok(Some(token.clone()));
         }
 
         if !CONFIG.token.is_empty() {
@@ -76,15 +279,97 @@ pub trait GithubProvider: Send {
                     },
                 )?;
 
-                return Err("The token found in the config is invalid, so it has been removed. Please try again.".into());
+                return Err(GuessError::Auth(String::from("The token found in the config is invalid, so it has been removed. Please try again.")));
             }
 
             return Ok(Some(CONFIG.token.clone()));
         }
 
+        if let Some(token) = env_token(|key| env::var(key).ok()) {
+            Self::test_token_structure(&token)?;
+
+            if Self::validate_token(agent, &token).is_err() {
+                return Err(GuessError::Auth(String::from(
+                    "Invalid personal access token",
+                )));
+            }
+
+            return Ok(Some(token));
+        }
+
         Ok(None)
     }
 
+    /// Collect every token that should be rotated through: the one from
+    /// [`GithubProvider::apply_token`], plus those from `--tokens` or the
+    /// config, which are validated and stored in the same way.
+    fn apply_tokens(agent: &Agent) -> Result<Tokens>
+    where
+        Self: Sized,
+    {
+        let mut tokens: Vec<String> = Self::apply_token(agent)?.into_iter().collect();
+
+        if let Some(list) = &ARGS.tokens {
+            let list: Vec<String> = list
+                .split(',')
+                .map(str::trim)
+                .filter(|token| !token.is_empty())
+                .map(String::from)
+                .collect();
+
+            for token in &list {
+                Self::test_token_structure(token)?;
+
+                if Self::validate_token(agent, token).is_err() {
+                    return Err(GuessError::Auth(String::from(
+                        "Invalid personal access token",
+                    )));
+                }
+            }
+
+            // The config is reloaded because [`GithubProvider::apply_token`]
+            // may have just changed it.
+            confy::store(
+                "guess-that-lang",
+                Config {
+                    tokens: list.clone(),
+                    ..confy::load("guess-that-lang")?
+                },
+            )?;
+
+            tokens.extend(list);
+        } else if !CONFIG.tokens.is_empty() {
+            let (valid, invalid): (Vec<_>, Vec<_>) = CONFIG
+                .tokens
+                .iter()
+                .cloned()
+                .partition(|token| Self::validate_token(agent, token).is_ok());
+
+            if !invalid.is_empty() {
+                confy::store(
+                    "guess-that-lang",
+                    Config {
+                        tokens: valid,
+                        ..confy::load("guess-that-lang")?
+                    },
+                )?;
+
+                return Err(GuessError::Auth(String::from("Some tokens found in the config are invalid, so they have been removed. Please try again.")));
+            }
+
+            tokens.extend(valid);
+        }
+
+        let mut unique = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            if !unique.contains(&token) {
+                unique.push(token);
+            }
+        }
+
+        Ok(Tokens::new(unique))
+    }
+
     /// Test a Github personal access token via regex.
     fn test_token_structure(token: &str) -> Result<()>
     where
@@ -93,7 +378,9 @@ pub trait GithubProvider: Send {
         if TOKEN_REGEX.is_match(token) {
             Ok(())
         } else {
-            Err("Invalid personal access token".into())
+            Err(GuessError::Auth(String::from(
+                "Invalid personal access token",
+            )))
         }
     }
 
@@ -106,11 +393,295 @@ pub trait GithubProvider: Send {
         agent
             .get(&format!("{GITHUB_BASE_URL}/rate_limit"))
             .with_authentication(Some(token))
-            .call()
-            .map_err(Into::into)
+            .call_counted()
+    }
+}
+
+/// The tokens a provider authenticates with. When one is ratelimited, the
+/// next one is used.
+#[derive(Default)]
+pub struct Tokens {
+    list: Vec<String>,
+    current: AtomicUsize,
+    /// The `x-ratelimit-remaining` header of the last response.
+    remaining: Mutex<Option<u32>>,
+}
+
+impl Tokens {
+    #[must_use]
+    pub fn new(tokens: Vec<String>) -> Self {
+        Self {
+            list: tokens,
+            current: AtomicUsize::new(0),
+            remaining: Mutex::new(None),
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// The token that's currently in use.
+    #[must_use]
+    pub fn current(&self) -> Option<&String> {
+        self.list
+            .get(self.current.load(Ordering::Relaxed) % self.list.len().max(1))
+    }
+
+    /// The number of requests the current token can make before being
+    /// ratelimited, according to the last response.
+    #[must_use]
+    pub fn remaining(&self) -> Option<u32> {
+        self.remaining.lock().ok().and_then(|remaining| *remaining)
+    }
+
+    /// Make the request created by `request` with the current token. If the
+    /// token turns out to be ratelimited, the request is retried with the next
+    /// token until every token has been tried, and then again after waiting
+    /// for the ratelimit to reset (see [`RATELIMIT_RETRIES`]).
+    pub fn call(&self, request: impl Fn(Option<&String>) -> Request) -> Result<Response> {
+        self.send(request, CountedCallExt::call_counted)
+    }
+
+    /// Like [`Tokens::call`], but with `body` sent as JSON.
+    pub fn send_json(
+        &self,
+        request: impl Fn(Option<&String>) -> Request,
+        body: &serde_json::Value,
+    ) -> Result<Response> {
+        self.send(request, |request| request.send_json_counted(body))
+    }
+
+    /// Create the request with `request` and make it with `send`, switching
+    /// tokens when one is ratelimited and waiting when all of them are.
+    fn send(
+        &self,
+        request: impl Fn(Option<&String>) -> Request,
+        send: impl Fn(Request) -> Result<Response>,
+    ) -> Result<Response> {
+        for _ in 1..self.list.len() {
+            match self.send_once(&request, &send) {
+                Err(err) if is_ratelimited(&err) => {
+                    self.current.fetch_add(1, Ordering::Relaxed);
+                }
+                result => return result,
+            }
+        }
+
+        let mut retries = 0;
+        loop {
+            let result = self.send_once(&request, &send);
+            let wait = match result.as_ref().err().and_then(ratelimited_response) {
+                Some(response) => ratelimit_wait(response, unix_time()),
+                None => return result,
+            };
+
+            if retries == RATELIMIT_RETRIES {
+                return Err(GuessError::Ratelimited(String::from(if self.is_empty() {
+                    "Github's ratelimit was reached. Pass a personal access token (--token) to make more requests."
+                } else {
+                    "Github's ratelimit was reached. Try again later, or pass more tokens (--tokens) to switch between."
+                })));
+            }
+
+            retries += 1;
+            thread::sleep(wait);
+        }
+    }
+
+    /// Make the request with the current token, and remember how many
+    /// requests are left.
+    fn send_once(
+        &self,
+        request: impl Fn(Option<&String>) -> Request,
+        send: impl Fn(Request) -> Result<Response>,
+    ) -> Result<Response> {
+        let result = send(request(self.current()));
+        let response = match &result {
+            Ok(response) => Some(response),
+            Err(GuessError::Network(err)) => match err.as_ref() {
+                ureq::Error::Status(_, response) => Some(response),
+                ureq::Error::Transport(_) => None,
+            },
+            Err(_) => None,
+        };
+
+        if let Some(remaining) = response
+            .and_then(|response| response.header("x-ratelimit-remaining"))
+            .and_then(|remaining| remaining.parse().ok())
+        {
+            if let Ok(mut last) = self.remaining.lock() {
+                *last = Some(remaining);
+            }
+
+            RATELIMIT_REMAINING.store(remaining, Ordering::Relaxed);
+        }
+
+        result
+    }
+}
+
+/// Get the first non-empty token from [`TOKEN_ENV_VARS`], using `var` to read
+/// them.
+pub fn env_token(var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    TOKEN_ENV_VARS
+        .iter()
+        .filter_map(|key| var(key))
+        .map(|token| token.trim().to_string())
+        .find(|token| !token.is_empty())
+}
+
+/// What Github answered while polling for the token of `guess-that-lang login`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DevicePoll {
+    /// The code hasn't been entered yet.
+    Pending,
+    /// Polling is too fast, and has to wait this many more seconds between
+    /// requests.
+    SlowDown(u64),
+    /// The code was entered, and this is the token.
+    Granted(String),
+}
+
+/// Read a response from [`ACCESS_TOKEN_URL`].
+pub fn parse_device_poll(response: &serde_json::Value) -> Result<DevicePoll> {
+    if let Some(token) = response["access_token"].as_str() {
+        return Ok(DevicePoll::Granted(token.to_string()));
+    }
+
+    match response["error"].as_str() {
+        Some("authorization_pending") => Ok(DevicePoll::Pending),
+        Some("slow_down") => Ok(DevicePoll::SlowDown(
+            response["interval"].as_u64().unwrap_or(5),
+        )),
+        Some("expired_token") => Err(GuessError::Auth(String::from(
+            "The code expired before it was entered. Please try again.",
+        ))),
+        Some("access_denied") => Err(GuessError::Auth(String::from("Signing in was cancelled."))),
+        error => Err(GuessError::Auth(format!(
+            "Github couldn't sign you in: {}",
+            response["error_description"]
+                .as_str()
+                .or(error)
+                .unwrap_or("unknown error")
+        ))),
+    }
+}
+
+/// Sign in with Github's device flow: print a code to enter on Github, wait
+/// until it's entered, and store the token in the config like `--token`
+/// would.
+pub fn login() -> Result<()> {
+    let client_id = OAUTH_CLIENT_ID.ok_or_else(|| {
+        GuessError::Config(String::from(
+            "This build of the game can't sign in with Github. Pass a personal access token (--token) instead.",
+        ))
+    })?;
+
+    let agent = RepositoryProvider::get_agent();
+    let device: serde_json::Value = agent
+        .post(DEVICE_CODE_URL)
+        .set("Accept", "application/json")
+        .send_form(&[("client_id", client_id)])?
+        .into_json()?;
+
+    let (device_code, user_code, uri) = match (
+        device["device_code"].as_str(),
+        device["user_code"].as_str(),
+        device["verification_uri"].as_str(),
+    ) {
+        (Some(device_code), Some(user_code), Some(uri)) => (device_code, user_code, uri),
+        _ => {
+            return Err(GuessError::Auth(String::from(
+                "Github didn't give a code to sign in with",
+            )))
+        }
+    };
+
+    println!("Go to {uri} and enter the code {user_code} to sign in.");
+
+    let mut interval = device["interval"].as_u64().unwrap_or(5);
+    let token = loop {
+        thread::sleep(Duration::from_secs(interval));
+
+        let response: serde_json::Value = agent
+            .post(ACCESS_TOKEN_URL)
+            .set("Accept", "application/json")
+            .send_form(&[
+                ("client_id", client_id),
+                ("device_code", device_code),
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+            ])?
+            .into_json()?;
+
+        match parse_device_poll(&response)? {
+            DevicePoll::Pending => {}
+            DevicePoll::SlowDown(new_interval) => interval = new_interval,
+            DevicePoll::Granted(token) => break token,
+        }
+    };
+
+    confy::store(
+        "guess-that-lang",
+        Config {
+            token,
+            ..confy::load("guess-that-lang")?
+        },
+    )?;
+
+    println!("Signed in! The token has been stored in the config.");
+    Ok(())
+}
+
+/// Whether an error is a response saying the ratelimit has been exhausted.
+fn is_ratelimited(err: &GuessError) -> bool {
+    ratelimited_response(err).is_some()
+}
+
+/// Get the response from an error saying the ratelimit has been exhausted,
+/// either the main one or a secondary one (which sets `Retry-After`).
+fn ratelimited_response(err: &GuessError) -> Option<&Response> {
+    match err {
+        GuessError::Network(err) => match err.as_ref() {
+            ureq::Error::Status(403 | 429, response)
+                if response.header("x-ratelimit-remaining") == Some("0")
+                    || response.header("retry-after").is_some() =>
+            {
+                Some(response)
+            }
+            _ => None,
+        },
+        _ => None,
     }
 }
 
+/// Get how long to wait for a ratelimit to reset from the `Retry-After` or
+/// `X-RateLimit-Reset` header of `response`, capped at
+/// [`MAX_RATELIMIT_WAIT`]. `now` is the current unix time in seconds.
+#[must_use]
+pub fn ratelimit_wait(response: &Response, now: u64) -> Duration {
+    let seconds = response
+        .header("retry-after")
+        .and_then(|seconds| seconds.parse().ok())
+        .or_else(|| {
+            response
+                .header("x-ratelimit-reset")
+                .and_then(|reset| reset.parse::<u64>().ok())
+                .map(|reset| reset.saturating_sub(now))
+        })
+        .unwrap_or(MAX_RATELIMIT_WAIT.as_secs());
+
+    Duration::from_secs(seconds).min(MAX_RATELIMIT_WAIT)
+}
+
+/// Get the current unix time in seconds.
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
 pub trait AuthenticationExt {
     #[must_use]
     fn with_authentication<S: AsRef<str>>(self, token: Option<S>) -> Self;
@@ -125,6 +696,26 @@ impl AuthenticationExt for Request {
     }
 }
 
+pub trait CountedCallExt {
+    /// Make the request, incrementing [`API_CALLS`].
+    fn call_counted(self) -> Result<Response>;
+
+    /// Send `body` as JSON, incrementing [`API_CALLS`].
+    fn send_json_counted(self, body: &serde_json::Value) -> Result<Response>;
+}
+
+impl CountedCallExt for Request {
+    fn call_counted(self) -> Result<Response> {
+        API_CALLS.fetch_add(1, Ordering::Relaxed);
+        self.call().map_err(Into::into)
+    }
+
+    fn send_json_counted(self, body: &serde_json::Value) -> Result<Response> {
+        API_CALLS.fetch_add(1, Ordering::Relaxed);
+        self.send_json(body).map_err(Into::into)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -136,14 +727,80 @@ mod tests {
             Ok(Self {})
         }
 
-        fn get_code(&mut self) -> Result<CodeData> {
+        fn get_code(&mut self, _languages: &[&'static str], _rng: &mut StdRng) -> Result<CodeData> {
             Ok(CodeData {
-                code: String::from(""),
-                language: String::from(""),
+                code: String::new(),
+                language: String::new(),
+                content_type: None,
+                source: None,
             })
         }
     }
 
+    #[test]
+    fn registered_providers() {
+        let names: Vec<_> = PROVIDERS.iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names,
+            [
+                "repos",
+                "gists",
+                "trending",
+                "local",
+                "gitlab",
+                "sourcegraph"
+            ]
+        );
+
+        match create("nope") {
+            Err(GuessError::Config(message)) => assert_eq!(
+                message,
+                "Unknown provider \"nope\", valid values: repos, gists, trending, local, gitlab, sourcegraph"
+            ),
+            _ => panic!("expected a config error"),
+        }
+
+        assert!(list().starts_with("repos (default)\ngists\n"));
+    }
+
+    #[test]
+    fn env_token_precedence() {
+        let env = |vars: &'static [(&str, &str)]| {
+            env_token(|key| {
+                vars.iter()
+                    .find(|(name, _)| *name == key)
+                    .map(|(_, value)| String::from(*value))
+            })
+        };
+
+        assert_eq!(env(&[]), None);
+        assert_eq!(env(&[("GH_TOKEN", "b")]), Some(String::from("b")));
+        assert_eq!(
+            env(&[("GH_TOKEN", "b"), ("GITHUB_TOKEN", "a")]),
+            Some(String::from("a"))
+        );
+        assert_eq!(
+            env(&[("GITHUB_TOKEN", " "), ("GH_TOKEN", "b")]),
+            Some(String::from("b"))
+        );
+    }
+
+    #[test]
+    fn ratelimit_reset() {
+        let wait = |headers: &str| {
+            let response: Response = format!("HTTP/1.1 403 Forbidden\r\n{headers}\r\n")
+                .parse()
+                .unwrap();
+            ratelimit_wait(&response, 1000)
+        };
+
+        assert_eq!(wait("Retry-After: 30\r\n"), Duration::from_secs(30));
+        assert_eq!(wait("X-RateLimit-Reset: 1010\r\n"), Duration::from_secs(10));
+        assert_eq!(wait("X-RateLimit-Reset: 900\r\n"), Duration::ZERO);
+        assert_eq!(wait("X-RateLimit-Reset: 5000\r\n"), MAX_RATELIMIT_WAIT);
+        assert_eq!(wait(""), MAX_RATELIMIT_WAIT);
+    }
+
     #[test]
     fn access_token_regex() {
         assert!(TestProvider::test_token_structure(&"a".repeat(40)).is_ok());
@@ -152,10 +809,70 @@ mod tests {
         assert!(TestProvider::test_token_structure(&format!("ghp_{}", ".".repeat(36))).is_err());
         assert!(TestProvider::test_token_structure(&format!("ghp_{}", "a".repeat(35))).is_err());
         assert!(TestProvider::test_token_structure(&format!("ghp_{}", "a".repeat(36))).is_ok());
+        assert!(TestProvider::test_token_structure(&format!("gho_{}", "a".repeat(36))).is_ok());
+    }
+
+    #[test]
+    fn device_flow_responses() {
+        let poll = |json: &str| parse_device_poll(&serde_json::from_str(json).unwrap());
+
+        assert_eq!(
+            poll(r#"{"error": "authorization_pending"}"#).unwrap(),
+            DevicePoll::Pending
+        );
+        assert_eq!(
+            poll(r#"{"error": "slow_down", "interval": 10}"#).unwrap(),
+            DevicePoll::SlowDown(10)
+        );
+        assert_eq!(
+            poll(r#"{"access_token": "gho_abc", "token_type": "bearer"}"#).unwrap(),
+            DevicePoll::Granted(String::from("gho_abc"))
+        );
+        assert!(matches!(
+            poll(r#"{"error": "expired_token"}"#),
+            Err(GuessError::Auth(_))
+        ));
+        assert!(matches!(
+            poll(r#"{"error": "incorrect_client_credentials"}"#),
+            Err(GuessError::Auth(message)) if message.ends_with("incorrect_client_credentials")
+        ));
+    }
+
+    #[test]
+    fn date_formatting() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(11_016), "2000-02-29");
+        assert_eq!(format_date(19_000), "2022-01-08");
+    }
+
+    #[test]
+    fn blank_code() {
+        let data = |code: &str| CodeData {
+            code: code.to_string(),
+            language: String::from("Rust"),
+            content_type: None,
+            source: None,
+        };
+
+        assert!(data("").is_blank());
+        assert!(data(" \n\t\r\n").is_blank());
+        assert!(!data("\nfn main() {}\n").is_blank());
+    }
+
+    #[test]
+    fn duration_parsing() {
+        assert_eq!(parse_days("30d").unwrap(), 30);
+        assert_eq!(parse_days("2w").unwrap(), 14);
+        assert_eq!(parse_days("1Y").unwrap(), 365);
+        assert!(parse_days("").is_err());
+        assert!(parse_days("30").is_err());
+        assert!(parse_days("d").is_err());
+        assert!(parse_days("-1d").is_err());
+        assert!(parse_days("5x").is_err());
     }
 
     #[allow(dead_code)]
-    #[ignore]
+    #[ignore = "requires network access"]
     fn invalid_token() {
         assert!(TestProvider::validate_token(&TestProvider::get_agent(), "invalid").is_err());
     }
@@ -0,0 +1,128 @@
+use serde::Deserialize;
+use ureq::serde_json;
+
+/// The language Github reports Jupyter notebooks (`.ipynb` files) as. Their
+/// content is JSON, so the code has to be extracted with [`extract`].
+pub const NOTEBOOK_LANGUAGE: &str = "Jupyter Notebook";
+
+#[derive(Deserialize)]
+pub struct Notebook {
+    #[serde(default)]
+    pub metadata: NotebookMetadata,
+    pub cells: Vec<NotebookCell>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct NotebookMetadata {
+    pub kernelspec: Option<Kernelspec>,
+    pub language_info: Option<LanguageInfo>,
+}
+
+#[derive(Deserialize)]
+pub struct Kernelspec {
+    pub language: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct LanguageInfo {
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct NotebookCell {
+    pub cell_type: String,
+    pub source: CellSource,
+}
+
+/// The source of a cell, which can be a single string or a list of lines.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum CellSource {
+    Text(String),
+    Lines(Vec<String>),
+}
+
+impl CellSource {
+    #[must_use]
+    pub fn text(&self) -> String {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Lines(lines) => lines.concat(),
+        }
+    }
+}
+
+/// Extract the code cells from a notebook, along with the language of its
+/// kernel. This is [`None`] if the notebook is invalid, has no code, or its
+/// language isn't one of `languages`.
+#[must_use]
+pub fn extract(json: &str, languages: &[&'static str]) -> Option<(String, String)> {
+    let notebook: Notebook = serde_json::from_str(json).ok()?;
+    let kernel_language = notebook
+        .metadata
+        .kernelspec
+        .and_then(|kernelspec| kernelspec.language)
+        .or_else(|| notebook.metadata.language_info.map(|info| info.name))?;
+
+    // Kernels use lowercase names, like "python".
+    let language = languages
+        .iter()
+        .find(|language| language.eq_ignore_ascii_case(&kernel_language))?;
+
+    let code = notebook
+        .cells
+        .iter()
+        .filter(|cell| cell.cell_type == "code")
+        .map(|cell| cell.source.text())
+        .filter(|source| !source.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    if code.is_empty() {
+        None
+    } else {
+        Some(((*language).to_string(), code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::LANGUAGES;
+    use indoc::indoc;
+
+    #[test]
+    fn notebook_extraction() {
+        let notebook = indoc! {r##"
+            {
+                "cells": [
+                    { "cell_type": "markdown", "metadata": {}, "source": ["# Title"] },
+                    {
+                        "cell_type": "code",
+                        "metadata": {},
+                        "outputs": [],
+                        "source": ["import os\n", "print(os.getcwd())"]
+                    },
+                    { "cell_type": "code", "metadata": {}, "outputs": [], "source": "" },
+                    { "cell_type": "code", "metadata": {}, "outputs": [], "source": "x = 1" }
+                ],
+                "metadata": { "kernelspec": { "language": "python", "name": "python3" } },
+                "nbformat": 4,
+                "nbformat_minor": 5
+            }
+        "##};
+
+        assert_eq!(
+            extract(notebook, &LANGUAGES),
+            Some((
+                String::from("Python"),
+                String::from("import os\nprint(os.getcwd())\n\nx = 1")
+            ))
+        );
+
+        let unsupported = notebook.replace("python", "julia");
+        assert_eq!(extract(&unsupported, &LANGUAGES), None);
+        assert_eq!(extract(notebook, &["Rust", "Go"]), None);
+        assert_eq!(extract("not json", &LANGUAGES), None);
+    }
+}
@@ -0,0 +1,101 @@
+use rand::{rngs::StdRng, seq::SliceRandom};
+use serde::Deserialize;
+use syntect::dumps;
+
+use crate::{
+    error::GuessError,
+    providers::{CodeData, GithubProvider},
+    Result,
+};
+
+/// A snippet from the pack bundled with the game.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Snippet {
+    pub language: String,
+    pub code: String,
+}
+
+/// Load the snippets bundled with the game. They're written in
+/// `assets/snippets/snippets.toml` and packed into a compressed dump by
+/// `assets/snippets/pack.py`.
+pub fn load_snippets() -> Result<Vec<Snippet>> {
+    dumps::from_reader(&include_bytes!("../../assets/dumps/snippets.dump")[..])
+        .map_err(|err| GuessError::Other(err.to_string()))
+}
+
+/// Gets code from the snippet pack bundled with the game, so it can be played
+/// without any network access (`--offline`).
+pub struct OfflineProvider {
+    /// The snippets that haven't been played yet. Once every snippet in the
+    /// requested languages has been played, they're all played again.
+    snippets: Vec<Snippet>,
+}
+
+impl GithubProvider for OfflineProvider {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            snippets: load_snippets()?,
+        })
+    }
+
+    fn get_code(&mut self, languages: &[&'static str], rng: &mut StdRng) -> Result<CodeData> {
+        let is_wanted = |snippet: &Snippet| languages.contains(&snippet.language.as_str());
+
+        if !self.snippets.iter().any(is_wanted) {
+            self.snippets = load_snippets()?;
+        }
+
+        let candidates: Vec<_> = self
+            .snippets
+            .iter()
+            .enumerate()
+            .filter(|(_, snippet)| is_wanted(snippet))
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let idx = *candidates.choose(rng).ok_or_else(|| {
+            GuessError::NoSnippet(String::from(
+                "No bundled snippets are written in the chosen languages",
+            ))
+        })?;
+
+        let snippet = self.snippets.swap_remove(idx);
+        Ok(CodeData {
+            code: snippet.code,
+            language: snippet.language,
+            content_type: None,
+            source: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::LANGUAGES;
+    use rand::SeedableRng;
+
+    #[test]
+    fn snippet_pack() {
+        let snippets = load_snippets().unwrap();
+
+        for language in LANGUAGES {
+            assert!(
+                snippets.iter().any(|snippet| snippet.language == language),
+                "no snippets for {language}"
+            );
+        }
+        assert!(snippets
+            .iter()
+            .all(|snippet| LANGUAGES.contains(&snippet.language.as_str())));
+        assert!(snippets
+            .iter()
+            .all(|snippet| !snippet.code.trim().is_empty()));
+
+        let mut provider = OfflineProvider { snippets };
+        let code = provider
+            .get_code(&["Rust"], &mut StdRng::seed_from_u64(0))
+            .unwrap();
+        assert_eq!(code.language, "Rust");
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        mpsc::{self, Receiver},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    game::PRELOAD_RATELIMIT_THRESHOLD,
+    providers::{CodeData, GithubProvider},
+    random::{self, SharedRng},
+    Result,
+};
+
+/// How many snippets are kept ready, besides the one the worker is waiting to
+/// hand over.
+pub const PRELOAD_BUFFER: usize = 2;
+
+/// A provider that can be used by the worker of a [`Preloader`] and the game
+/// at the same time.
+pub type SharedProvider = Arc<Mutex<Box<dyn GithubProvider>>>;
+
+/// Fetches code on a worker thread, so that a few snippets are always ready
+/// and rounds don't wait on a slow provider. The worker stops after an error,
+/// when the ratelimit is nearly exhausted (see
+/// [`PRELOAD_RATELIMIT_THRESHOLD`]), or once the preloader is dropped.
+pub struct Preloader {
+    receiver: Receiver<Result<CodeData>>,
+    /// The languages the worker fetches code in, which change as the game
+    /// balances them (see [`Preloader::want`]).
+    languages: Arc<Mutex<Vec<&'static str>>>,
+    /// Snippets that were preloaded but weren't wanted when they were taken,
+    /// e.g. because their language was just played.
+    held: VecDeque<CodeData>,
+}
+
+impl Preloader {
+    /// Start fetching code in one of `languages` from `provider`, with the
+    /// game's `rng`.
+    #[must_use]
+    pub fn start(provider: SharedProvider, languages: Vec<&'static str>, rng: SharedRng) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(PRELOAD_BUFFER);
+        let languages = Arc::new(Mutex::new(languages));
+        let wanted = Arc::clone(&languages);
+
+        thread::spawn(move || loop {
+            let languages = match wanted.lock() {
+                Ok(languages) => languages.clone(),
+                Err(_) => return,
+            };
+
+            let data = match provider.lock() {
+                Ok(mut provider) => {
+                    let remaining = provider.ratelimit_remaining();
+                    if remaining.map_or(false, |remaining| remaining < PRELOAD_RATELIMIT_THRESHOLD)
+                    {
+                        return;
+                    }
+
+                    provider.get_code(&languages, &mut random::lock(&rng))
+                }
+                Err(_) => return,
+            };
+
+            // Sending fails once the preloader is dropped.
+            let is_err = data.is_err();
+            if sender.send(data).is_err() || is_err {
+                return;
+            }
+        });
+
+        Self {
+            receiver,
+            languages,
+            held: VecDeque::new(),
+        }
+    }
+
+    /// Fetch code in one of `languages` from now on. Code that was already
+    /// fetched is still handed out when it's wanted.
+    pub fn want(&self, languages: Vec<&'static str>) {
+        if let Ok(mut wanted) = self.languages.lock() {
+            *wanted = languages;
+        }
+    }
+
+    /// Take preloaded code in one of `wanted`, waiting for the worker if none
+    /// is ready. Code in other languages is held for later, up to
+    /// [`PRELOAD_BUFFER`] snippets. This returns [`None`] once the worker has
+    /// stopped, in which case code has to be fetched directly.
+    pub fn take(&mut self, wanted: &[&'static str]) -> Option<Result<CodeData>> {
+        let is_wanted = |data: &CodeData| wanted.contains(&data.language.as_str());
+
+        if let Some(idx) = self.held.iter().position(is_wanted) {
+            return self.held.remove(idx).map(Ok);
+        }
+
+        loop {
+            match self.receiver.recv().ok()? {
+                Ok(data) if !is_wanted(&data) => {
+                    self.held.push_back(data);
+                    if self.held.len() > PRELOAD_BUFFER {
+                        self.held.pop_front();
+                    }
+                }
+                result => return Some(result),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::GuessError, random::Rngs};
+    use rand::rngs::StdRng;
+
+    /// Hands out snippets in the given languages in order, then fails.
+    struct ListProvider(VecDeque<&'static str>);
+
+    impl GithubProvider for ListProvider {
+        fn new() -> Result<Self> {
+            Ok(Self(VecDeque::new()))
+        }
+
+        fn get_code(&mut self, _languages: &[&'static str], _rng: &mut StdRng) -> Result<CodeData> {
+            let language = self
+                .0
+                .pop_front()
+                .ok_or_else(|| GuessError::NoSnippet(String::from("out of snippets")))?;
+
+            Ok(CodeData {
+                code: String::from("code"),
+                language: language.to_string(),
+                content_type: None,
+                source: None,
+            })
+        }
+    }
+
+    #[test]
+    fn preloaded_snippets() {
+        let provider = ListProvider(VecDeque::from(["Rust", "Go", "C"]));
+        let provider: SharedProvider = Arc::new(Mutex::new(Box::new(provider)));
+        let mut preloader =
+            Preloader::start(provider, vec!["Rust", "Go", "C"], Rngs::new(0).content);
+
+        let language = |data: Option<Result<CodeData>>| data.unwrap().unwrap().language;
+
+        // Rust is held until it's wanted.
+        assert_eq!(language(preloader.take(&["Go", "C"])), "Go");
+        assert_eq!(language(preloader.take(&["Rust", "C"])), "Rust");
+        assert_eq!(language(preloader.take(&["C"])), "C");
+        assert!(matches!(preloader.take(&["C"]), Some(Err(_))));
+        assert!(preloader.take(&["C"]).is_none());
+    }
+
+    #[test]
+    fn preload_wanted_languages() {
+        /// Hands out a snippet in the first of the requested languages.
+        struct FirstProvider;
+
+        impl GithubProvider for FirstProvider {
+            fn new() -> Result<Self> {
+                Ok(Self)
+            }
+
+            fn get_code(
+                &mut self,
+                languages: &[&'static str],
+                _rng: &mut StdRng,
+            ) -> Result<CodeData> {
+                Ok(CodeData {
+                    code: String::from("code"),
+                    language: languages[0].to_string(),
+                    content_type: None,
+                    source: None,
+                })
+            }
+        }
+
+        let provider: SharedProvider = Arc::new(Mutex::new(Box::new(FirstProvider)));
+        let mut preloader = Preloader::start(provider, vec!["Rust"], Rngs::new(0).content);
+        preloader.want(vec!["Go"]);
+
+        // Snippets fetched before the change are held, and the worker then
+        // fetches Go.
+        let data = preloader.take(&["Go"]).unwrap().unwrap();
+        assert_eq!(data.language, "Go");
+    }
+}
@@ -1,17 +1,37 @@
 // Inspired by https://github.com/ModProg/language-guesser.
 
-use std::collections::HashMap;
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    thread,
+    time::{Duration, Instant},
+};
 
-use rand::{seq::SliceRandom, thread_rng, Rng};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 use serde::Deserialize;
 use ureq::Agent;
 
 use crate::{
-    game::LANGUAGES,
-    providers::{AuthenticationExt, CodeData, GithubProvider, GITHUB_BASE_URL},
-    Result,
+    error::GuessError,
+    providers::{
+        days_ago,
+        graphql::{self, GRAPHQL_URL},
+        history, parse_days, AuthenticationExt, CodeData, GithubProvider, Tokens, GITHUB_BASE_URL,
+    },
+    Result, ARGS, CONFIG,
 };
 
+/// The maximum number of files to check the size of in
+/// [`RepositoryProvider::get_file`].
+pub const MAX_SIZE_ATTEMPTS: usize = 3;
+
+/// The minimum number of stars for repositories from
+/// [`RepositoryProvider::trending`].
+pub const TRENDING_MIN_STARS: u32 = 50;
+
+/// The error shown when the repos provider is used without a token.
+pub const REQUIRES_TOKEN: &str = "The repos and trending providers require a personal access token (--token). Use `--provider gists` to play without one.";
+
 #[derive(Deserialize)]
 pub struct Page<T> {
     pub items: Vec<T>,
@@ -25,94 +45,358 @@ pub struct Repository {
 #[derive(Deserialize)]
 pub struct RepositoryFilePreview {
     pub url: String,
+    pub path: String,
+}
+
+impl RepositoryFilePreview {
+    /// Whether the file looks like a test, according to
+    /// [`crate::Config::test_patterns`]. Patterns ending with `/` match a
+    /// directory anywhere in the path, and any others match part of the file
+    /// name.
+    #[must_use]
+    pub fn is_test(&self, patterns: &[String]) -> bool {
+        let (directories, name) = self.path.rsplit_once('/').unwrap_or(("", &self.path));
+
+        patterns
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('/') {
+                Some(directory) => directories.split('/').any(|part| part == directory),
+                None => name.contains(pattern.as_str()),
+            })
+    }
 }
 
 #[derive(Deserialize)]
 pub struct RepositoryFile {
+    pub url: String,
+    pub html_url: String,
     pub download_url: String,
+    pub size: u64,
+}
+
+impl RepositoryFile {
+    /// How many bytes the file is outside of the preferred size range.
+    #[must_use]
+    pub fn size_distance(&self) -> u64 {
+        size_distance(self.size)
+    }
+}
+
+/// How many bytes `size` is outside of the preferred size range.
+#[must_use]
+pub fn size_distance(size: u64) -> u64 {
+    ARGS.min_file_size.saturating_sub(size) + size.saturating_sub(ARGS.max_file_size)
+}
+
+/// Whether a file should only be used if there's nothing else, because it's a
+/// test (which is often boilerplate that isn't very representative of the
+/// language) or was played recently.
+fn is_skipped(preview: &RepositoryFilePreview) -> bool {
+    (!ARGS.include_tests && preview.is_test(&CONFIG.test_patterns))
+        || history::is_seen(&preview.url)
 }
 
 pub struct RepositoryProvider<'a> {
     agent: Agent,
-    token: Option<String>,
+    tokens: Tokens,
     cache: HashMap<&'a str, Vec<String>>,
+    /// The search qualifiers used to find repositories, besides the language.
+    qualifiers: String,
+    /// The number of result pages to randomly choose from.
+    pages: u32,
+    /// When the last search request was made, used to throttle them.
+    last_search: Cell<Option<Instant>>,
 }
 
 impl RepositoryProvider<'_> {
+    /// Create a provider that gets code from recently created repositories
+    /// with the most stars, rather than from recently updated ones.
+    pub fn trending() -> Result<Self> {
+        let since = days_ago(ARGS.since);
+
+        Ok(Self {
+            qualifiers: format!(
+                "created:>{since} stars:>{TRENDING_MIN_STARS} sort:stars{}",
+                Self::age_qualifier()?
+            ),
+            // There are much less results for these qualifiers.
+            pages: 3,
+            ..Self::new()?
+        })
+    }
+
+    /// Get the qualifier that filters out repositories that haven't been
+    /// pushed to within `--max-file-age`, if it's provided.
+    pub fn age_qualifier() -> Result<String> {
+        match &ARGS.max_file_age {
+            Some(age) => Ok(format!(" pushed:>{}", days_ago(parse_days(age)?))),
+            None => Ok(String::new()),
+        }
+    }
+
+    /// Get how long to wait before the next search request so that they're
+    /// at least `interval` apart.
+    #[must_use]
+    pub fn search_delay(
+        last_search: Option<Instant>,
+        now: Instant,
+        interval: Duration,
+    ) -> Duration {
+        last_search.map_or(Duration::ZERO, |last_search| {
+            interval.saturating_sub(now.saturating_duration_since(last_search))
+        })
+    }
+
+    /// Wait until a search request can be made. Github's search API has a much
+    /// stricter ratelimit than the rest of it, which is easy to exhaust in a
+    /// burst with two searches per round plus preloading, so search requests
+    /// are spaced out by `--search-interval` instead of waiting for a 403.
+    pub fn throttle_search(&self) {
+        let interval = Duration::from_millis(ARGS.search_interval);
+        thread::sleep(Self::search_delay(
+            self.last_search.get(),
+            Instant::now(),
+            interval,
+        ));
+
+        self.last_search.set(Some(Instant::now()));
+    }
+
     /// Get a vec of random valid gists on Github. This is used with the assumption
     /// that at least one valid gist will be found.
-    pub fn get_repos(&self, language: &str) -> Result<Vec<String>> {
+    pub fn get_repos(&self, language: &str, rng: &mut StdRng) -> Result<Vec<String>> {
+        let page = rng.gen_range(0..self.pages).to_string();
+        self.throttle_search();
         let mut repos: Vec<_> = self
-            .agent
-            .get(&format!("{GITHUB_BASE_URL}/search/repositories"))
-            .query("page", &thread_rng().gen_range(0..35).to_string())
-            .query("q", &format!("language:{language} stars:>20 sort:updated"))
-            .with_authentication(self.token.as_ref())
-            .call()?
+            .tokens
+            .call(|token| {
+                self.agent
+                    .get(&format!("{GITHUB_BASE_URL}/search/repositories"))
+                    .query("page", &page)
+                    .query("q", &format!("language:{language} {}", self.qualifiers))
+                    .with_authentication(token)
+            })?
             .into_json::<Page<Repository>>()?
             .items
             .into_iter()
             .map(|repo| repo.full_name)
             .collect();
 
-        repos.shuffle(&mut thread_rng());
+        repos.shuffle(rng);
 
         Ok(repos)
     }
 
-    pub fn get_file(&self, language: &str, name: &str) -> Result<RepositoryFile> {
+    pub fn get_file(&self, language: &str, name: &str, rng: &mut StdRng) -> Result<RepositoryFile> {
+        self.throttle_search();
         let files = self
-            .agent
-            .get(&format!("{GITHUB_BASE_URL}/search/code"))
-            .query("q", &format!("language:{language} repo:{name}"))
-            .with_authentication(self.token.as_ref())
-            .call()?
+            .tokens
+            .call(|token| {
+                self.agent
+                    .get(&format!("{GITHUB_BASE_URL}/search/code"))
+                    .query("q", &format!("language:{language} repo:{name}"))
+                    .with_authentication(token)
+            })?
             .into_json::<Page<RepositoryFilePreview>>()?
             .items;
 
-        let preview = files.choose(&mut thread_rng()).ok_or("")?;
+        let (skipped, mut files): (Vec<_>, Vec<_>) = files.into_iter().partition(is_skipped);
+
+        if files.is_empty() {
+            files = skipped;
+        }
+
+        let preferred_size = ARGS.min_file_size..=ARGS.max_file_size;
+        let mut best: Option<RepositoryFile> = None;
 
-        Ok(self
-            .agent
-            .get(&preview.url)
-            .with_authentication(self.token.as_ref())
-            .call()?
-            .into_json::<_>()?)
+        // The search results don't include file sizes, so they have to be
+        // fetched one by one. Only a few are tried to save on API calls, after
+        // which the file closest to the preferred size range is used.
+        let previews: Vec<_> = files.choose_multiple(rng, MAX_SIZE_ATTEMPTS).collect();
+
+        for preview in previews {
+            let file: RepositoryFile = self
+                .tokens
+                .call(|token| self.agent.get(&preview.url).with_authentication(token))?
+                .into_json()?;
+
+            if preferred_size.contains(&file.size) {
+                return Ok(file);
+            }
+
+            if best
+                .as_ref()
+                .map_or(true, |best| file.size_distance() < best.size_distance())
+            {
+                best = Some(file);
+            }
+        }
+
+        best.ok_or_else(|| GuessError::NoSnippet(format!("No {language} files found in {name}")))
+    }
+
+    /// Get a random file from the top of a repository with a single GraphQL
+    /// request, instead of the several that [`RepositoryProvider::get_file`]
+    /// needs. Returns the file's URL and contents.
+    pub fn get_graphql_file(
+        &self,
+        language: &str,
+        name: &str,
+        rng: &mut StdRng,
+    ) -> Result<(String, String)> {
+        let body = graphql::tree_request(name)
+            .ok_or_else(|| GuessError::NoSnippet(format!("Invalid repository name {name}")))?;
+        let response = self
+            .tokens
+            .send_json(
+                |token| self.agent.post(GRAPHQL_URL).with_authentication(token),
+                &body,
+            )?
+            .into_json()?;
+
+        let files = graphql::get_files(response).into_iter().filter_map(|file| {
+            graphql::is_language(&file.path, language).then(|| {
+                let preview = RepositoryFilePreview {
+                    url: format!("https://github.com/{name}/blob/HEAD/{}", file.path),
+                    path: file.path,
+                };
+
+                (preview, file.size, file.text)
+            })
+        });
+
+        let (skipped, mut files): (Vec<_>, Vec<_>) =
+            files.partition(|(preview, ..)| is_skipped(preview));
+
+        if files.is_empty() {
+            files = skipped;
+        }
+
+        // Shuffling first picks randomly between files that are equally close
+        // to the preferred size range.
+        files.shuffle(rng);
+        files
+            .into_iter()
+            .min_by_key(|(_, size, _)| size_distance(*size))
+            .map(|(preview, _, text)| (preview.url, text))
+            .ok_or_else(|| GuessError::NoSnippet(format!("No {language} files found in {name}")))
     }
 }
 
 impl GithubProvider for RepositoryProvider<'_> {
     fn new() -> Result<Self> {
         let agent = Self::get_agent();
-        let token = Self::apply_token(&agent)?;
+        let tokens = Self::apply_tokens(&agent)?;
+
+        // Github's code search API (used in [`RepositoryProvider::get_file`])
+        // rejects unauthenticated requests, so this provider can't work
+        // without a token.
+        if tokens.is_empty() {
+            return Err(GuessError::Auth(String::from(REQUIRES_TOKEN)));
+        }
 
         Ok(Self {
             agent,
-            token,
+            tokens,
             cache: HashMap::new(),
+            qualifiers: format!("stars:>20 sort:updated{}", Self::age_qualifier()?),
+            pages: 35,
+            last_search: Cell::new(None),
         })
     }
 
-    fn get_code(&mut self) -> Result<CodeData> {
-        let language = LANGUAGES.choose(&mut thread_rng()).unwrap();
+    fn ratelimit_remaining(&self) -> Option<u32> {
+        self.tokens.remaining()
+    }
+
+    fn get_code(&mut self, languages: &[&'static str], rng: &mut StdRng) -> Result<CodeData> {
+        let language = languages
+            .choose(rng)
+            .ok_or_else(|| GuessError::NoSnippet(String::from("No languages to choose from")))?;
         let cache = self.cache.get(language);
 
         if cache.map_or(true, Vec::is_empty) {
-            self.cache.insert(language, self.get_repos(language)?);
+            self.cache.insert(language, self.get_repos(language, rng)?);
         }
 
         let cache = self.cache.entry(language).or_default();
-        let repo = (*cache).pop().unwrap();
-        let file = self.get_file(language, &repo)?;
+        let repo = (*cache)
+            .pop()
+            .ok_or_else(|| GuessError::NoSnippet(format!("No {language} repositories found")))?;
 
-        Ok(CodeData {
-            code: self
-                .agent
+        if ARGS.graphql {
+            let (url, code) = self.get_graphql_file(language, &repo, rng)?;
+            history::record(&url);
+
+            return Ok(CodeData {
+                code,
+                language: (*language).to_string(),
+                content_type: None,
+                source: Some(url),
+            });
+        }
+
+        let file = self.get_file(language, &repo, rng)?;
+        history::record(&file.url);
+
+        let response = self.tokens.call(|token| {
+            self.agent
                 .get(&file.download_url)
-                .with_authentication(self.token.as_ref())
-                .call()?
-                .into_string()?,
+                .with_authentication(token)
+        })?;
+        let content_type = Some(response.content_type().to_string());
+
+        Ok(CodeData {
+            code: response.into_string()?,
             language: (*language).to_string(),
+            content_type,
+            source: Some(file.html_url),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn search_throttling() {
+        let last_search = Instant::now();
+        let interval = Duration::from_secs(2);
+        let delay = |since_last| {
+            RepositoryProvider::search_delay(Some(last_search), last_search + since_last, interval)
+        };
+
+        assert_eq!(
+            RepositoryProvider::search_delay(None, last_search, interval),
+            Duration::ZERO
+        );
+        assert_eq!(delay(Duration::ZERO), interval);
+        assert_eq!(
+            delay(Duration::from_millis(500)),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(delay(Duration::from_secs(5)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_file_detection() {
+        let patterns = Config::default_test_patterns();
+        let is_test = |path: &str| {
+            RepositoryFilePreview {
+                url: String::new(),
+                path: path.to_string(),
+            }
+            .is_test(&patterns)
+        };
+
+        assert!(is_test("src/parser_test.go"));
+        assert!(is_test("app/button.spec.ts"));
+        assert!(is_test("test/helpers.rb"));
+        assert!(is_test("crates/core/tests/parse.rs"));
+        assert!(!is_test("src/main.rs"));
+        assert!(!is_test("src/latest/main.rs"));
+        assert!(!is_test("tests.rs"));
+    }
+}
@@ -0,0 +1,196 @@
+use rand::{rngs::StdRng, seq::SliceRandom};
+use serde::Deserialize;
+use ureq::{serde_json::json, Agent};
+
+use crate::{
+    error::GuessError,
+    providers::{graphql, history, CodeData, CountedCallExt, GithubProvider},
+    Result,
+};
+
+pub const SOURCEGRAPH_BASE_URL: &str = "https://sourcegraph.com";
+
+/// The number of files asked for in each search.
+pub const SEARCH_COUNT: u32 = 50;
+
+/// Searches public code on every host Sourcegraph indexes, and gets each
+/// file's content and where it can be viewed.
+pub const SEARCH_QUERY: &str = "
+query($query: String!) {
+  search(query: $query, version: V3) {
+    results {
+      results {
+        ... on FileMatch { file { url content } }
+      }
+    }
+  }
+}
+";
+
+#[derive(Deserialize)]
+pub struct SearchData {
+    pub search: Option<Search>,
+}
+
+#[derive(Deserialize)]
+pub struct Search {
+    pub results: SearchResults,
+}
+
+#[derive(Deserialize)]
+pub struct SearchResults {
+    pub results: Vec<SearchResult>,
+}
+
+/// A search result, which only has a file if it's a file match.
+#[derive(Deserialize)]
+pub struct SearchResult {
+    pub file: Option<File>,
+}
+
+#[derive(Deserialize)]
+pub struct File {
+    /// The path of the file on Sourcegraph, relative to
+    /// [`SOURCEGRAPH_BASE_URL`].
+    pub url: String,
+    pub content: String,
+}
+
+impl SearchData {
+    /// Get the files that were found, with the URLs they can be viewed at.
+    #[must_use]
+    pub fn into_files(self) -> Vec<(String, String)> {
+        self.search
+            .map(|search| search.results.results)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|result| result.file)
+            .map(|file| (format!("{SOURCEGRAPH_BASE_URL}{}", file.url), file.content))
+            .collect()
+    }
+}
+
+/// Build a search for files written in `language`. Results come back in the
+/// same order every time, so only repositories whose owner starts with
+/// `initial` are searched to vary them.
+#[must_use]
+pub fn search_query(language: &str, initial: char) -> String {
+    format!(
+        r#"lang:"{language}" repo:^[^/]+/{initial} fork:no archived:no type:file count:{SEARCH_COUNT} timeout:10s"#
+    )
+}
+
+/// Gets code from Sourcegraph's public code search, which covers code from
+/// Github, GitLab, and other hosts, and doesn't count towards Github's
+/// ratelimit. No token is needed.
+pub struct SourcegraphProvider {
+    agent: Agent,
+    /// Files that haven't been played yet, with their URLs, contents, and
+    /// languages.
+    cache: Vec<(String, String, &'static str)>,
+}
+
+impl SourcegraphProvider {
+    /// Search for files written in `language` that haven't been played
+    /// recently.
+    pub fn search(
+        &self,
+        language: &'static str,
+        rng: &mut StdRng,
+    ) -> Result<Vec<(String, String, &'static str)>> {
+        let initial = *b"abcdefghijklmnopqrstuvwxyz".choose(rng).unwrap_or(&b'a');
+
+        let response: graphql::Response<SearchData> = self
+            .agent
+            .post(&format!("{SOURCEGRAPH_BASE_URL}/.api/graphql"))
+            .send_json_counted(&json!({
+                "query": SEARCH_QUERY,
+                "variables": { "query": search_query(language, char::from(initial)) },
+            }))?
+            .into_json()?;
+
+        let mut files: Vec<_> = response
+            .data
+            .map(SearchData::into_files)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(url, _)| !history::is_seen(url))
+            .map(|(url, content)| (url, content, language))
+            .collect();
+
+        files.shuffle(rng);
+
+        Ok(files)
+    }
+}
+
+impl GithubProvider for SourcegraphProvider {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            agent: Self::get_agent(),
+            cache: Vec::new(),
+        })
+    }
+
+    fn get_code(&mut self, languages: &[&'static str], rng: &mut StdRng) -> Result<CodeData> {
+        if !self
+            .cache
+            .iter()
+            .any(|(_, _, language)| languages.contains(language))
+        {
+            let language = *languages.choose(rng).ok_or_else(|| {
+                GuessError::NoSnippet(String::from("No languages were requested"))
+            })?;
+
+            self.cache = self.search(language, rng)?;
+        }
+
+        let idx = self
+            .cache
+            .iter()
+            .position(|(_, _, language)| languages.contains(language))
+            .ok_or_else(|| {
+                GuessError::NoSnippet(String::from("No files were found on Sourcegraph"))
+            })?;
+
+        let (url, code, language) = self.cache.swap_remove(idx);
+        history::record(&url);
+
+        Ok(CodeData {
+            code,
+            language: language.to_string(),
+            content_type: None,
+            source: Some(url),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ureq::serde_json;
+
+    #[test]
+    fn search_results() {
+        assert_eq!(
+            search_query("C#", 'q'),
+            r#"lang:"C#" repo:^[^/]+/q fork:no archived:no type:file count:50 timeout:10s"#
+        );
+
+        let data: SearchData = serde_json::from_value(json!({
+            "search": { "results": { "results": [
+                { "file": { "url": "/github.com/a/b/-/blob/main.rs", "content": "fn main() {}" } },
+                {},
+            ] } }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            data.into_files(),
+            [(
+                String::from("https://sourcegraph.com/github.com/a/b/-/blob/main.rs"),
+                String::from("fn main() {}")
+            )]
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use ureq::Agent;
+
+use crate::{
+    error::GuessError,
+    providers::{local, CodeData, GithubProvider},
+    terminal::Terminal,
+    Result, ARGS,
+};
+
+/// Plays the code at exactly one URL given with `--url`, e.g. for teaching or
+/// reproducing a bug. The game ends after that round.
+pub struct UrlProvider {
+    agent: Agent,
+    url: String,
+    language: &'static str,
+    is_played: bool,
+}
+
+/// Get the language of the file at `url` from its extension, if it's one of
+/// [`crate::game::LANGUAGES`]. The query and fragment are ignored.
+#[must_use]
+pub fn get_language(url: &str) -> Option<&'static str> {
+    let path = url.split(['?', '#']).next()?;
+    local::get_language(&Terminal::load_syntaxes().ok()?, Path::new(path))
+}
+
+impl GithubProvider for UrlProvider {
+    fn new() -> Result<Self> {
+        let url = ARGS
+            .url
+            .clone()
+            .ok_or_else(|| GuessError::Config(String::from("No URL was given (--url)")))?;
+
+        let language = get_language(&url).ok_or_else(|| {
+            GuessError::Config(format!(
+                "The language of {url} isn't supported, or couldn't be told from its extension"
+            ))
+        })?;
+
+        Ok(Self {
+            agent: Self::get_agent(),
+            url,
+            language,
+            is_played: false,
+        })
+    }
+
+    /// Get the code at the URL, whichever languages are wanted. It's only
+    /// fetched once, so there's nothing else to play if it can't be played.
+    fn get_code(&mut self, _languages: &[&'static str], _rng: &mut StdRng) -> Result<CodeData> {
+        if self.is_played {
+            return Err(GuessError::NoSnippet(format!(
+                "The code at {} can't be played, e.g. because it's only comments",
+                self.url
+            )));
+        }
+
+        self.is_played = true;
+
+        let response = self.agent.get(&self.url).call()?;
+        let content_type = Some(response.content_type().to_string());
+
+        Ok(CodeData {
+            code: response.into_string()?,
+            language: self.language.to_string(),
+            content_type,
+            source: Some(self.url.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_language() {
+        let url = "https://raw.githubusercontent.com/rust-lang/rust/master/src/main.rs";
+        assert_eq!(get_language(url), Some("Rust"));
+        assert_eq!(
+            get_language("https://example.com/app.py?raw=true#L1"),
+            Some("Python")
+        );
+        assert_eq!(get_language("https://example.com/notes.txt"), None);
+        assert_eq!(get_language("https://example.com/"), None);
+    }
+}
@@ -0,0 +1,98 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use lazy_static::lazy_static;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{game::Checkpoint, ARGS};
+
+/// The stream of the generator for what's played (see [`Rngs::content`]).
+const CONTENT_STREAM: u64 = 0;
+
+/// The stream of the generator for how code is revealed (see
+/// [`Rngs::display`]).
+const DISPLAY_STREAM: u64 = 1;
+
+lazy_static! {
+    /// The seed from `--seed`, or the one of the game being resumed, or a
+    /// random one if there isn't one, so that the game can still be shared
+    /// with a challenge code.
+    static ref SEED: u64 = ARGS
+        .seed
+        .or_else(Checkpoint::resumed_seed)
+        .unwrap_or_else(rand::random);
+}
+
+/// A generator that's shared between the game and the threads it fetches
+/// code on.
+pub type SharedRng = Arc<Mutex<StdRng>>;
+
+/// The generators a game is played with, which are held by the game and
+/// passed to everything that makes random choices. With `--seed`, the same
+/// choices are made in every game.
+#[derive(Clone)]
+pub struct Rngs {
+    /// The generator for what's played: which code is fetched and the
+    /// options for it.
+    pub content: SharedRng,
+    /// The generator for how code is revealed. This is kept separate from
+    /// [`Rngs::content`] because rounds are revealed while the next one is
+    /// being fetched on another thread, which would otherwise make the order
+    /// they're used in vary between games.
+    pub display: SharedRng,
+}
+
+impl Rngs {
+    /// Create the generators for a game played with `seed`.
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            content: Arc::new(Mutex::new(seeded(seed, CONTENT_STREAM))),
+            display: Arc::new(Mutex::new(seeded(seed, DISPLAY_STREAM))),
+        }
+    }
+}
+
+/// Create a generator from `seed` for `stream`. They're kept in separate
+/// bytes of the generator's seed, so that no two pairs of them give the same
+/// generator.
+#[must_use]
+pub fn seeded(seed: u64, stream: u64) -> StdRng {
+    let mut bytes = [0; 32];
+    bytes[..8].copy_from_slice(&seed.to_le_bytes());
+    bytes[8..16].copy_from_slice(&stream.to_le_bytes());
+    StdRng::from_seed(bytes)
+}
+
+/// The seed the game is played with (see [`SEED`]).
+#[must_use]
+pub fn seed() -> u64 {
+    *SEED
+}
+
+/// Lock `rng`, which can still be used if a thread panicked while holding
+/// it.
+pub fn lock(rng: &SharedRng) -> MutexGuard<'_, StdRng> {
+    rng.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn seeded_streams() {
+        let sample = |seed, stream| {
+            let mut rng = seeded(seed, stream);
+            (0..8).map(|_| rng.gen::<u32>()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(sample(42, CONTENT_STREAM), sample(42, CONTENT_STREAM));
+        assert_ne!(sample(42, CONTENT_STREAM), sample(43, CONTENT_STREAM));
+        assert_ne!(sample(42, CONTENT_STREAM), sample(42, DISPLAY_STREAM));
+
+        // Neighbouring seeds don't share a stream.
+        assert_ne!(sample(42, DISPLAY_STREAM), sample(43, CONTENT_STREAM));
+    }
+}
@@ -0,0 +1,46 @@
+/// The multipliers for the points awarded with `--streak-bonus`, along with
+/// how many answers in a row have to be correct before they apply.
+pub const STREAK_MULTIPLIERS: [(u32, f32); 4] = [(2, 1.1), (4, 1.25), (6, 1.5), (10, 2.0)];
+
+/// Get the multiplier for the points awarded after `streak` correct answers in
+/// a row, from [`STREAK_MULTIPLIERS`].
+#[must_use]
+pub fn streak_multiplier(streak: u32) -> f32 {
+    STREAK_MULTIPLIERS
+        .iter()
+        .rev()
+        .find(|(min_streak, _)| streak >= *min_streak)
+        .map_or(1.0, |(_, multiplier)| *multiplier)
+}
+
+/// Multiply the available points by `multiplier`, rounded to the nearest
+/// point.
+#[must_use]
+pub fn apply_multiplier(points: f32, multiplier: f32) -> u32 {
+    (points * multiplier).round().max(0.0) as u32
+}
+
+/// Format a multiplier like `x1.25`.
+#[must_use]
+pub fn format_multiplier(multiplier: f32) -> String {
+    format!("x{multiplier}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streak_multipliers() {
+        let multipliers: Vec<_> = [0, 1, 2, 3, 4, 6, 9, 10, 50]
+            .into_iter()
+            .map(streak_multiplier)
+            .collect();
+
+        assert_eq!(multipliers, [1.0, 1.0, 1.1, 1.1, 1.25, 1.5, 1.5, 2.0, 2.0]);
+        assert_eq!(apply_multiplier(90.0, 1.25), 113);
+        assert_eq!(apply_multiplier(70.0, 1.1), 77);
+        assert_eq!(format_multiplier(1.25), "x1.25");
+        assert_eq!(format_multiplier(2.0), "x2");
+    }
+}
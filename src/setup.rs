@@ -0,0 +1,149 @@
+use std::io::{self, BufRead, Write};
+
+use crossterm::{style::Stylize, tty::IsTty};
+
+use crate::{
+    providers::{repos::RepositoryProvider, GithubProvider, PROVIDERS},
+    terminal::{ask, ThemeStyle},
+    Config, Result, DEFAULT_WAIT,
+};
+
+/// Whether the game is being played for the first time in a terminal, in which
+/// case the setup is started before playing. Both stdin and stdout have to be
+/// a terminal, so that the questions can be seen and answered. This loads the
+/// config itself, so that [`crate::CONFIG`] is only loaded once the setup is
+/// done.
+#[must_use]
+pub fn is_first_run() -> bool {
+    io::stdin().is_tty()
+        && io::stdout().is_tty()
+        && confy::load::<Config>("guess-that-lang")
+            .map_or(false, |config| !config.set_up && config.high_score == 0)
+}
+
+/// Walk through choosing a theme, provider, wait time, and token with
+/// `--setup`, and store the choices in the config.
+pub fn run() -> Result<()> {
+    let config: Config = confy::load("guess-that-lang")?;
+    let stdin = io::stdin();
+    let config = ask_config(&mut stdin.lock(), &mut io::stdout(), config)?;
+
+    confy::store("guess-that-lang", config)?;
+    println!(
+        "\n{}\n",
+        "Saved! Run guess-that-lang --setup to change these later.".green()
+    );
+
+    Ok(())
+}
+
+/// Ask for each setting with [`ask`], starting from `config`.
+pub fn ask_config(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+    config: Config,
+) -> Result<Config> {
+    writeln!(
+        writer,
+        "{}\nPress Enter to keep the value in brackets.\n",
+        "Welcome to guess-that-lang!".bold()
+    )?;
+
+    let theme = ask(
+        reader,
+        writer,
+        "Theme (dark/light/auto)",
+        Some(match config.theme {
+            Some(ThemeStyle::Dark) => "dark",
+            Some(ThemeStyle::Light) => "light",
+            None => "auto",
+        }),
+        |theme| match theme {
+            "auto" => Ok(None),
+            theme => ThemeStyle::try_from(Some(theme.to_string()))
+                .map(Some)
+                .map_err(|()| format!("\"{theme}\" isn't dark, light, or auto")),
+        },
+    )?;
+
+    let names: Vec<_> = PROVIDERS.iter().map(|(name, _)| *name).collect();
+    let provider = ask(
+        reader,
+        writer,
+        &format!("Where to get code from ({})", names.join("/")),
+        Some(config.provider.as_deref().unwrap_or(names[0])),
+        |provider| {
+            names
+                .contains(&provider)
+                .then(|| provider.to_string())
+                .ok_or_else(|| format!("\"{provider}\" isn't one of {}", names.join(", ")))
+        },
+    )?;
+
+    let wait = ask(
+        reader,
+        writer,
+        "How many ms to wait before revealing code",
+        Some(&config.wait.unwrap_or(DEFAULT_WAIT).to_string()),
+        |wait| {
+            wait.parse::<u64>()
+                .map_err(|_| format!("\"{wait}\" isn't a number of ms"))
+        },
+    )?;
+
+    let token = ask(
+        reader,
+        writer,
+        "A Github personal access token, to make more requests (Enter to skip)",
+        (!config.token.is_empty()).then_some("keep"),
+        |token| match token {
+            "" => Ok(None),
+            "keep" => Ok(Some(config.token.clone())),
+            token => validate_token(token).map(|()| Some(token.to_string())),
+        },
+    )?;
+
+    // A bundled theme or theme file is only replaced by choosing dark or
+    // light.
+    let (theme_name, theme_file) = if theme.is_some() {
+        (None, None)
+    } else {
+        (config.theme_name.clone(), config.theme_file.clone())
+    };
+
+    Ok(Config {
+        theme,
+        theme_name,
+        theme_file,
+        provider: Some(provider),
+        wait: Some(wait),
+        token: token.unwrap_or_default(),
+        set_up: true,
+        ..config
+    })
+}
+
+/// Check that a token is well-formed and accepted by Github.
+fn validate_token(token: &str) -> std::result::Result<(), String> {
+    RepositoryProvider::test_token_structure(token).map_err(|err| err.to_string())?;
+    RepositoryProvider::validate_token(&RepositoryProvider::get_agent(), token)
+        .map(|_| ())
+        .map_err(|err| format!("Github didn't accept that token: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setup_answers() {
+        let mut input = io::Cursor::new("light\nfoo\ngists\n\n\n");
+        let config = ask_config(&mut input, &mut Vec::new(), Config::default()).unwrap();
+
+        assert!(matches!(config.theme, Some(ThemeStyle::Light)));
+        assert_eq!(config.provider.as_deref(), Some("gists"));
+        assert_eq!(config.wait, Some(DEFAULT_WAIT));
+        assert!(config.token.is_empty());
+        assert!(config.set_up);
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dialects,
+    game::{Accuracy, GameMode},
+    strings::{fill, STRINGS},
+    Config, ARGS, CONFIG,
+};
+
+/// The name of the file that [`Stats`] are stored in, next to the config.
+pub const STATS_NAME: &str = "guess-that-lang-stats";
+
+/// How many answers were correct out of how many were given, for each language
+/// that has come up, as they used to be stored in the config.
+pub type LegacyStats = BTreeMap<String, (u32, u32)>;
+
+/// How well answers have gone for one language, across sessions.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(default)]
+pub struct LanguageStats {
+    pub correct: u32,
+    pub answered: u32,
+    /// The points won in rounds of this language, minus lost wagers.
+    pub points: i64,
+    /// How many answers in a row have been correct.
+    pub streak: u32,
+    pub best_streak: u32,
+}
+
+impl LanguageStats {
+    /// The average points won in a round, rounded to the nearest point.
+    #[must_use]
+    pub fn average_points(&self) -> Option<i64> {
+        (self.answered > 0).then(|| {
+            let answered = i64::from(self.answered);
+            (self.points * 2 + self.points.signum() * answered) / (answered * 2)
+        })
+    }
+}
+
+/// Lifetime performance, shown with `--stats`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, Default)]
+#[serde(default)]
+pub struct Stats {
+    pub languages: BTreeMap<String, LanguageStats>,
+    /// How many answers in a row have been correct, in any language.
+    pub streak: u32,
+    pub best_streak: u32,
+}
+
+impl Stats {
+    /// Load the stats, moving over the accuracy that used to be stored in
+    /// the config. Failing to load them shouldn't stop the game, so they're
+    /// empty in that case.
+    #[must_use]
+    pub fn load() -> Self {
+        let stats: Self = confy::load(STATS_NAME).unwrap_or_default();
+        if !stats.languages.is_empty() || CONFIG.stats.is_empty() {
+            return stats;
+        }
+
+        let stats = Self::from_legacy(&CONFIG.stats);
+        stats.save();
+
+        if let Ok(config) = confy::load::<Config>("guess-that-lang") {
+            let _config = confy::store(
+                "guess-that-lang",
+                Config {
+                    stats: LegacyStats::new(),
+                    ..config
+                },
+            );
+        }
+
+        stats
+    }
+
+    /// Save the stats, ignoring errors like [`crate::game::Game::save_checkpoint`].
+    pub fn save(&self) {
+        let _stats = confy::store(STATS_NAME, self);
+    }
+
+    /// Convert the accuracy that used to be stored in the config. Points and
+    /// streaks weren't recorded, so they start from zero.
+    #[must_use]
+    pub fn from_legacy(legacy: &LegacyStats) -> Self {
+        let languages = legacy
+            .iter()
+            .map(|(language, &(correct, answered))| {
+                let stats = LanguageStats {
+                    correct,
+                    answered,
+                    ..LanguageStats::default()
+                };
+
+                (language.clone(), stats)
+            })
+            .collect();
+
+        Self {
+            languages,
+            ..Self::default()
+        }
+    }
+
+    /// The number of rounds that have been answered.
+    #[must_use]
+    pub fn rounds_played(&self) -> u32 {
+        self.languages.values().map(|stats| stats.answered).sum()
+    }
+
+    /// Record an answer to a round in `language` that won `points`. Dialects
+    /// count towards their language.
+    pub fn record(&mut self, language: &str, was_correct: bool, points: i64) {
+        let stats = self
+            .languages
+            .entry(dialects::base_language(language).to_string())
+            .or_default();
+
+        stats.answered += 1;
+        stats.points += points;
+
+        for (streak, best_streak) in [
+            (&mut stats.streak, &mut stats.best_streak),
+            (&mut self.streak, &mut self.best_streak),
+        ] {
+            if was_correct {
+                *streak += 1;
+                *best_streak = (*best_streak).max(*streak);
+            } else {
+                *streak = 0;
+            }
+        }
+
+        if was_correct {
+            stats.correct += 1;
+        }
+    }
+}
+
+/// Record an answer in the stats file. Answers in category mode aren't
+/// recorded since they aren't about a language.
+pub fn save_answer(language: &str, was_correct: bool, points: i64) {
+    if ARGS.mode == GameMode::Category {
+        return;
+    }
+
+    let mut stats = Stats::load();
+    stats.record(language, was_correct, points);
+    stats.save();
+}
+
+/// Format the stats as a table, from the least to the most accurate language,
+/// followed by the totals. Languages that are equally accurate are ordered by
+/// how often they came up.
+#[must_use]
+pub fn format_table(stats: &Stats) -> String {
+    if stats.languages.is_empty() {
+        return STRINGS.no_stats.clone();
+    }
+
+    let mut rows: Vec<_> = stats.languages.iter().collect();
+    rows.sort_by(|(_, a), (_, b)| {
+        // Comparing `a.correct / a.answered` to `b.correct / b.answered`
+        // without dividing.
+        (a.correct * b.answered)
+            .cmp(&(b.correct * a.answered))
+            .then_with(|| b.answered.cmp(&a.answered))
+    });
+
+    let header = [
+        STRINGS.language.clone(),
+        STRINGS.stats_accuracy.clone(),
+        STRINGS.stats_average.clone(),
+        STRINGS.stats_streak.clone(),
+    ];
+
+    let cells: Vec<_> = [header]
+        .into_iter()
+        .chain(rows.into_iter().map(|(language, language_stats)| {
+            let accuracy = Accuracy {
+                correct: language_stats.correct,
+                answered: language_stats.answered,
+                ..Accuracy::default()
+            };
+            let average = language_stats
+                .average_points()
+                .map_or_else(|| String::from("-"), |average| average.to_string());
+
+            [
+                language.clone(),
+                accuracy.to_string(),
+                average,
+                language_stats.best_streak.to_string(),
+            ]
+        }))
+        .collect();
+
+    let widths: Vec<_> = (0..4)
+        .map(|column| {
+            cells
+                .iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let lines = cells.iter().map(|row| {
+        row.iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    });
+
+    let summary = fill(
+        &fill(&STRINGS.stats_summary, stats.rounds_played()),
+        stats.best_streak,
+    );
+
+    lines
+        .chain([String::new(), summary])
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_table() {
+        let mut stats = Stats::default();
+        assert_eq!(format_table(&stats), STRINGS.no_stats);
+
+        for (language, was_correct, points) in [
+            ("Rust", true, 90),
+            ("Rust", true, 60),
+            ("Go", false, 0),
+            ("Go", true, 81),
+            ("Python 3", false, -25),
+            ("C", true, 100),
+        ] {
+            stats.record(language, was_correct, points);
+        }
+
+        let python = stats.languages["Python"];
+        assert_eq!(
+            (python.correct, python.answered, python.points),
+            (0, 1, -25)
+        );
+        assert_eq!(stats.languages["Rust"].best_streak, 2);
+        assert_eq!(stats.languages["Go"].average_points(), Some(41));
+        assert_eq!(stats.languages.len(), 4);
+        assert_eq!(
+            (stats.rounds_played(), stats.streak, stats.best_streak),
+            (6, 1, 2)
+        );
+
+        let table = format_table(&stats);
+        let languages: Vec<_> = table
+            .lines()
+            .skip(1)
+            .take(4)
+            .map(|line| line.split_whitespace().next().unwrap())
+            .collect();
+
+        assert_eq!(languages, ["Python", "Go", "Rust", "C"]);
+        assert!(table.contains("Go        50% (1/2)   41"));
+        assert!(table.ends_with(&fill(&fill(&STRINGS.stats_summary, 6), 2)));
+    }
+
+    #[test]
+    fn legacy_stats() {
+        let legacy = LegacyStats::from([(String::from("Rust"), (3, 4))]);
+        let stats = Stats::from_legacy(&legacy);
+
+        assert_eq!(stats.rounds_played(), 4);
+        assert_eq!(stats.languages["Rust"].correct, 3);
+        assert_eq!(stats.languages["Rust"].average_points(), Some(0));
+    }
+}
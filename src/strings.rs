@@ -0,0 +1,203 @@
+use std::{fmt::Display, path::Path, process};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::ARGS;
+
+lazy_static! {
+    pub static ref STRINGS: Strings = Strings::load();
+}
+
+/// All user-facing strings, so that the game can be translated with
+/// `--lang-file`. A `{}` in a string is replaced with a value using
+/// [`fill`]. Strings missing from a translation fall back to English.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Strings {
+    pub prompt: String,
+    pub category_prompt: String,
+    pub bonus_prompt: String,
+    pub same_language: String,
+    pub different_languages: String,
+    pub typed_prompt: String,
+    pub high_score: String,
+    pub total_points: String,
+    pub available_points: String,
+    pub accuracy: String,
+    pub wager: String,
+    pub hint: String,
+    pub hint_cost: String,
+    pub hint_paradigm: String,
+    pub hint_keyword: String,
+    pub hint_file: String,
+    pub quit: String,
+    pub replay: String,
+    pub review_source: String,
+    pub review_continue: String,
+    pub correct: String,
+    pub incorrect: String,
+    pub wagered: String,
+    pub speed_bonus: String,
+    pub streak_bonus: String,
+    pub scored: String,
+    pub requests: String,
+    pub beat_high_score: String,
+    pub new_high_score: String,
+    pub summary_points: String,
+    pub summary_languages: String,
+    pub share: String,
+    pub challenge_code: String,
+    pub reasonable: String,
+    pub language: String,
+    pub stats_accuracy: String,
+    pub stats_average: String,
+    pub stats_streak: String,
+    pub stats_summary: String,
+    pub no_stats: String,
+    pub lines_clamped: String,
+    pub skipped_empty: String,
+    pub paused: String,
+    pub rounds_correct: String,
+    pub ended_early: String,
+    pub time_left: String,
+    pub study_countdown: String,
+    pub time_up: String,
+    pub lives: String,
+    pub out_of_lives: String,
+    pub requests_left: String,
+    pub ratelimit_warning: String,
+    pub fetching_rounds: String,
+    pub loading_syntaxes: String,
+    pub cache_fallback: String,
+    pub offline_fallback: String,
+    pub unknown_answer: String,
+}
+
+impl Default for Strings {
+    fn default() -> Self {
+        Self {
+            prompt: String::from(
+                "Which programming language is this? (Type the corresponding number)",
+            ),
+            bonus_prompt: String::from(
+                "Bonus round! Are these written in the same language? (Double points)",
+            ),
+            same_language: String::from("Same language"),
+            different_languages: String::from("Different languages"),
+            category_prompt: String::from(
+                "What kind of program is this? (Type the corresponding number)",
+            ),
+            typed_prompt: String::from("Type your answer and press Enter (Esc to quit)"),
+            high_score: String::from("High Score:"),
+            total_points: String::from("Total Points:"),
+            available_points: String::from("Available Points:"),
+            accuracy: String::from("Accuracy:"),
+            wager: String::from("Wager [w]:"),
+            hint: String::from("Hint [h]:"),
+            hint_cost: String::from("{} points each"),
+            hint_paradigm: String::from("Kind: {}"),
+            hint_keyword: String::from("Keyword: {}"),
+            hint_file: String::from("File: {}"),
+            quit: String::from("Quit"),
+            review_source: String::from("Source: {}"),
+            review_continue: String::from("Press any key to continue"),
+            replay: String::from("Replay"),
+            correct: String::from("Correct"),
+            incorrect: String::from("Incorrect"),
+            wagered: String::from("{} wagered"),
+            speed_bonus: String::from("{} speed bonus"),
+            streak_bonus: String::from("Streak Bonus:"),
+            scored: String::from("You scored {} points!"),
+            requests: String::from("You made {} Github requests."),
+            beat_high_score: String::from("You beat your high score of {}!"),
+            new_high_score: String::from("That's your new high score!"),
+            summary_points: String::from("Points per round:"),
+            summary_languages: String::from("By language:"),
+            share: String::from("Share it: {}"),
+            challenge_code: String::from("Challenge a friend to the same game with --challenge {}"),
+            reasonable: String::from("These were reasonable guesses too:"),
+            language: String::from("Language"),
+            stats_accuracy: String::from("Accuracy"),
+            stats_average: String::from("Avg. Points"),
+            stats_streak: String::from("Best Streak"),
+            stats_summary: String::from(
+                "{} rounds played, with a best streak of {} correct answers in a row.",
+            ),
+            no_stats: String::from("No answers have been recorded yet."),
+            lines_clamped: String::from(
+                "Code was cut off at {} lines to fit in the terminal. Make it taller to see more.",
+            ),
+            skipped_empty: String::from("Skipped an empty file: {}"),
+            paused: String::from("[paused]"),
+            rounds_correct: String::from("You answered {} of {} rounds correctly."),
+            ended_early: String::from("The game ended after {} of {} rounds."),
+            time_left: String::from("Time left: {}"),
+            study_countdown: String::from("({}s left)"),
+            time_up: String::from("Time's up!"),
+            lives: String::from("Lives:"),
+            out_of_lives: String::from("You ran out of lives."),
+            requests_left: String::from("{} Github requests left"),
+            ratelimit_warning: String::from(
+                "Only {} Github requests are left, so the next round isn't being preloaded.",
+            ),
+            fetching_rounds: String::from("Fetching rounds... {}"),
+            loading_syntaxes: String::from("Loading syntaxes..."),
+            cache_fallback: String::from("Github can't be reached, so cached code is played"),
+            offline_fallback: String::from("Github can't be reached, so bundled code is played"),
+            unknown_answer: String::from("\"{}\" isn't one of the possible answers."),
+        }
+    }
+}
+
+impl Strings {
+    /// Load the strings from `--lang-file`, or use the English ones if it
+    /// isn't provided. An invalid file is reported like an invalid argument.
+    #[must_use]
+    pub fn load() -> Self {
+        let path = match &ARGS.lang_file {
+            Some(path) => Path::new(path),
+            None => return Self::default(),
+        };
+
+        // `confy::load_path` would create the file if it doesn't exist.
+        if !path.is_file() {
+            eprintln!("Invalid language file: {} doesn't exist", path.display());
+            process::exit(1);
+        }
+
+        confy::load_path(path).unwrap_or_else(|err| {
+            eprintln!("Invalid language file: {err}");
+            process::exit(1);
+        })
+    }
+}
+
+/// Replace the `{}` in a string from [`Strings`] with `value`.
+#[must_use]
+pub fn fill(template: &str, value: impl Display) -> String {
+    template.replacen("{}", &value.to_string(), 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_translation() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/locales/es.toml");
+        let spanish: Strings = confy::load_path(path).unwrap();
+        let english = Strings::default();
+
+        assert_ne!(spanish.prompt, english.prompt);
+        assert_ne!(spanish.share, english.share);
+        assert_eq!(fill(&spanish.scored, 100), "¡Conseguiste 100 puntos!");
+    }
+
+    #[test]
+    fn filling() {
+        assert_eq!(fill("You scored {} points!", 5), "You scored 5 points!");
+        assert_eq!(fill("{} {}", "a"), "a {}");
+        assert_eq!(fill("Quit", 5), "Quit");
+    }
+}
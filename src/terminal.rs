@@ -1,10 +1,19 @@
 use std::{
-    env,
-    io::{stdout, Stdout, Write},
+    cmp,
+    collections::{BTreeMap, HashSet},
+    env, fs,
+    io::{self, stdout, BufRead, Write},
     ops::ControlFlow,
+    path::PathBuf,
     result,
-    sync::{mpsc::Receiver, Mutex},
-    time::Duration,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{Receiver, RecvTimeoutError},
+        Mutex, MutexGuard,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
 #[cfg(windows)]
@@ -16,23 +25,41 @@ use ansi_term::{
     Color::{self, Fixed, RGB},
 };
 use crossterm::{
-    cursor::{Hide, MoveTo, MoveToColumn, MoveUp, RestorePosition, SavePosition},
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    cursor::{Hide, MoveTo, MoveToColumn, RestorePosition, SavePosition},
+    event::{
+        self, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent,
+        MouseEventKind,
+    },
     execute, queue,
     style::{Print, Stylize},
-    terminal::{self, enable_raw_mode, EnterAlternateScreen},
+    terminal::{self, enable_raw_mode, Clear, ClearType, EnterAlternateScreen},
 };
-use rand::{seq::SliceRandom, thread_rng};
+use rand::{rngs::StdRng, seq::SliceRandom};
 use serde::{Deserialize, Serialize};
 use syntect::{
     dumps,
-    easy::HighlightLines,
+    easy::{HighlightLines, ScopeRangeIterator},
     highlighting::{self, Theme, ThemeSet},
-    parsing::SyntaxSet,
+    parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet},
     util::LinesWithEndings,
 };
-
-use crate::{game::PROMPT, Config, Result, ARGS, CONFIG};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{
+    challenge::Challenge,
+    error::GuessError,
+    export::RoundRecord,
+    game::{Accuracy, Difficulty, Game, GameMode, Round, Stakes},
+    hints::HINT_COST,
+    providers::{
+        cache::{self, Fallback},
+        CodeData, API_CALLS, RATELIMIT_REMAINING,
+    },
+    random::{self, SharedRng},
+    scoring, stats,
+    strings::{fill, STRINGS},
+    Config, Result, ARGS, CONFIG,
+};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub enum ThemeStyle {
@@ -61,57 +88,691 @@ impl From<ThemeStyle> for &'static str {
     }
 }
 
-pub struct Terminal {
+/// The environment variable that enables [`Capture`], containing the path to
+/// write the captured output to.
+pub const CAPTURE_ENV: &str = "GUESS_THAT_LANG_CAPTURE";
+
+/// The fewest rows code was cut off at this session because `--max-lines`
+/// didn't fit in the terminal, or [`usize::MAX`] if it always fit.
+pub static CLAMPED_LINES: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// The names of the syntaxes for languages whose name in
+/// [`crate::game::LANGUAGES`] (which is Github's) isn't the syntax's name.
+pub const SYNTAX_OVERRIDES: [(&str, &str); 1] = [("Assembly", "Assembly (x86_64)")];
+
+/// Syntaxes to use for sources that report a `Content-Type` but whose
+/// language name isn't known, e.g. raw URLs without a useful extension.
+pub const MIME_SYNTAXES: [(&str, &str); 24] = [
+    ("application/javascript", "JavaScript"),
+    ("text/javascript", "JavaScript"),
+    ("application/typescript", "TypeScript"),
+    ("text/x-typescript", "TypeScript"),
+    ("text/x-python", "Python"),
+    ("application/x-python-code", "Python"),
+    ("text/x-rustsrc", "Rust"),
+    ("text/rust", "Rust"),
+    ("application/x-sh", "Shell"),
+    ("text/x-sh", "Shell"),
+    ("text/x-shellscript", "Shell"),
+    ("text/css", "CSS"),
+    ("text/html", "HTML"),
+    ("text/x-c", "C"),
+    ("text/x-c++src", "C++"),
+    ("text/x-csharp", "C#"),
+    ("text/x-java", "Java"),
+    ("text/x-java-source", "Java"),
+    ("text/x-go", "Go"),
+    ("text/x-kotlin", "Kotlin"),
+    ("application/x-httpd-php", "PHP"),
+    ("text/x-ruby", "Ruby"),
+    ("application/sql", "SQL"),
+    ("text/x-lua", "Lua"),
+];
+
+/// The positions of everything printed by [`Terminal::print_round_info`],
+/// which every cursor movement in a round is based on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Layout {
+    /// The row the table with the points and code starts at.
+    pub table_row: u16,
+    /// The row of the first line of code.
+    pub code_row: u16,
+    /// The row of the first option.
+    pub option_row: u16,
+    /// The number of options, not counting the quit option.
+    pub option_count: usize,
+}
+
+impl Layout {
+    /// The width of the gutter, which holds the line numbers and the padding
+    /// before the points.
+    pub const GUTTER_WIDTH: usize = 7;
+
+    /// The column code starts at, after the gutter and "│ ".
+    pub const CODE_COLUMN: usize = Self::GUTTER_WIDTH + 2;
+
+    /// Get the number of rows of code that fit in a terminal `height` rows
+    /// tall with `option_count` options. At least one row is always allowed.
+    #[must_use]
+    pub fn max_code_rows(height: usize, option_count: usize) -> usize {
+        // The 9 rows in [`Layout::new`] besides the code and options, the quit
+        // option, and the row the cursor is left on.
+        height.saturating_sub(option_count + 11).max(1)
+    }
+
+    /// Compute the layout of a round with `code_len` lines of code and
+    /// `option_count` options, and a row for hints below the points with
+    /// `--hints`.
+    #[must_use]
+    pub fn new(
+        code_len: usize,
+        option_count: usize,
+        options_first: bool,
+        has_hint_row: bool,
+    ) -> Self {
+        // The prompt, a blank line, the options, the quit option, and another
+        // blank line.
+        let table_row = if options_first {
+            option_count as u16 + 4
+        } else {
+            0
+        };
+
+        // The top of the table, the 3 rows of points, the hint row, and the
+        // separator.
+        let code_row = table_row + 5 + u16::from(has_hint_row);
+
+        // The bottom of the table, a blank line, the prompt, and another
+        // blank line.
+        let option_row = if options_first {
+            2
+        } else {
+            code_row + code_len as u16 + 4
+        };
+
+        Self {
+            table_row,
+            code_row,
+            option_row,
+            option_count,
+        }
+    }
+
+    /// Get the row of the prompt, which is two rows above the options.
+    #[must_use]
+    pub fn prompt_row(&self) -> u16 {
+        self.option_row.saturating_sub(2)
+    }
+
+    /// Get the row of the quit option, which is right after the options.
+    #[must_use]
+    pub fn quit_row(&self) -> u16 {
+        self.option_row + self.option_count as u16
+    }
+
+    /// Get the row of the points at `idx` (high score, total, and available
+    /// points).
+    #[must_use]
+    pub fn points_row(&self, idx: u16) -> u16 {
+        self.table_row + 1 + idx
+    }
+
+    /// Get the column the points after `label` start at.
+    #[must_use]
+    pub fn points_column(label: &str) -> u16 {
+        // The label is preceded by the gutter and "│ ", and followed by a
+        // space.
+        (Self::CODE_COLUMN + label.chars().count() + 1) as u16
+    }
+
+    /// Get the position of the points after `label`, in the row at `idx` (see
+    /// [`Layout::points_row`]).
+    #[must_use]
+    pub fn points_position(&self, idx: u16, label: &str) -> MoveTo {
+        MoveTo(Self::points_column(label), self.points_row(idx))
+    }
+
+    /// Get the position of `column` in the code's `row`.
+    #[must_use]
+    pub fn code_position(&self, row: usize, column: usize) -> MoveTo {
+        MoveTo(
+            (Self::CODE_COLUMN + column) as u16,
+            self.code_row + row as u16,
+        )
+    }
+}
+
+/// How code is revealed during a round.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RevealMode {
+    /// Reveal a whole line at a time.
+    Lines,
+    /// Reveal a whole line at a time, starting from the last one.
+    BottomUp,
+    /// Reveal highlighted tokens from across the whole snippet at a time, so
+    /// its structure emerges evenly.
+    Tokens,
+}
+
+impl FromStr for RevealMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> result::Result<Self, Self::Err> {
+        match mode {
+            "lines" => Ok(Self::Lines),
+            "bottom-up" => Ok(Self::BottomUp),
+            "tokens" => Ok(Self::Tokens),
+            _ => Err(format!(
+                "Invalid reveal mode \"{mode}\" (expected lines, bottom-up, or tokens)"
+            )),
+        }
+    }
+}
+
+/// The colors used for the available points and for right and wrong answers.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Palette {
+    /// Green for good and red for bad.
+    #[default]
+    Normal,
+    /// Blue for good and orange for bad, which can be told apart with
+    /// red-green colorblindness.
+    Colorblind,
+    /// No colors, with symbols marking right and wrong answers instead.
+    Mono,
+}
+
+impl Palette {
+    /// The palette from `--palette`, falling back to the one used last.
+    #[must_use]
+    pub fn current() -> Self {
+        ARGS.palette.or(CONFIG.palette).unwrap_or_default()
+    }
+
+    /// Get the color for `value` on a gradient from bad (0) to good (100).
+    /// Right and wrong answers use the ends of the gradient.
+    #[must_use]
+    pub fn color(self, value: f32) -> Color {
+        let value = value.clamp(0.0, 100.0) / 100.0;
+
+        match self {
+            // https://stackoverflow.com/a/7947812/13721990
+            Self::Normal => Color::RGB(
+                255.0_f32.min(255.0 * 2.0 * (1.0 - value)) as u8,
+                255.0_f32.min(2.0 * 255.0 * value) as u8,
+                0,
+            ),
+            // From orange to blue in the Okabe-Ito palette.
+            Self::Colorblind => {
+                let mix = |bad: f32, good: f32| (bad + (good - bad) * value).round() as u8;
+                Color::RGB(mix(230.0, 0.0), mix(159.0, 114.0), mix(0.0, 178.0))
+            }
+            Self::Mono => Color::White,
+        }
+    }
+
+    /// Get the symbol shown before a right or wrong answer, which is only
+    /// needed when colors can't tell them apart.
+    #[must_use]
+    pub fn symbol(self, was_correct: bool) -> &'static str {
+        match (self, was_correct) {
+            (Self::Mono, true) => "✓ ",
+            (Self::Mono, false) => "✗ ",
+            _ => "",
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(palette: &str) -> result::Result<Self, Self::Err> {
+        match palette {
+            "normal" => Ok(Self::Normal),
+            "colorblind" => Ok(Self::Colorblind),
+            "mono" => Ok(Self::Mono),
+            _ => Err(format!(
+                "Invalid palette \"{palette}\" (expected normal, colorblind, or mono)"
+            )),
+        }
+    }
+}
+
+/// Highlights code a line at a time while keeping track of its scopes, so
+/// that comments can be recognized no matter how the theme colors them.
+pub struct CodeHighlighter<'a> {
+    highlighter: HighlightLines<'a>,
+    state: ParseState,
+    stack: ScopeStack,
+}
+
+impl<'a> CodeHighlighter<'a> {
+    #[must_use]
+    pub fn new(syntax: &SyntaxReference, theme: &'a Theme) -> Self {
+        Self {
+            highlighter: HighlightLines::new(syntax, theme),
+            state: ParseState::new(syntax),
+            stack: ScopeStack::new(),
+        }
+    }
+
+    /// Whether any part of `line` besides whitespace is inside a comment.
+    /// Every line has to be checked in order, so that comments spanning
+    /// multiple lines are recognized.
+    pub fn has_comment(&mut self, line: &str, syntaxes: &SyntaxSet) -> bool {
+        let comment = Scope::new("comment").unwrap();
+        let ops = match self.state.parse_line(line, syntaxes) {
+            Ok(ops) => ops,
+            Err(_) => return false,
+        };
+
+        let mut has_comment = false;
+        for (range, op) in ScopeRangeIterator::new(&ops, line) {
+            if self.stack.apply(op).is_err() {
+                break;
+            }
+
+            // Every operation is applied even after a comment is found, so
+            // the stack is correct for the next line.
+            has_comment |= !line[range].trim().is_empty()
+                && self
+                    .stack
+                    .as_slice()
+                    .iter()
+                    .any(|scope| comment.is_prefix_of(*scope));
+        }
+
+        has_comment
+    }
+}
+
+/// What the thread reading input tells the thread revealing code.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RevealSignal {
+    /// An answer was given, so no more code should be revealed.
+    Answered,
+    /// p or space was pressed to pause or resume revealing code.
+    TogglePause,
+}
+
+/// A line of parsed code.
+pub struct CodeLine {
+    /// The line as it was written, possibly truncated.
+    pub raw: String,
+    /// The line with syntax highlighting applied.
+    pub highlighted: String,
+    /// The highlighted pieces of the line and the columns they start at,
+    /// excluding whitespace. These are used by [`RevealMode::Tokens`].
+    pub tokens: Vec<(usize, String)>,
+    /// Whether this row continues the line before it, which was too wide for
+    /// the terminal and wrapped with `--wrap`.
+    pub is_continuation: bool,
+}
+
+impl CodeLine {
+    /// Whether the line is empty, which is the case for lines that are only
+    /// there to separate code.
+    #[must_use]
+    pub fn is_blank(&self) -> bool {
+        self.raw == "\n"
+    }
+}
+
+/// A writer that passes all output through to stdout while recording it, and
+/// dumps the recording to `path` when dropped. This makes it possible to diff
+/// the raw ANSI output of a game, e.g. to reproduce rendering bugs.
+pub struct Capture {
+    pub buffer: Vec<u8>,
+    pub path: PathBuf,
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        stdout().write_all(buf)?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        stdout().flush()
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        let _ = fs::write(&self.path, &self.buffer);
+    }
+}
+
+/// The terminal UI, which writes to stdout by default but can be used with
+/// any writer.
+pub struct Terminal<W = Box<dyn Write + Send>> {
     pub syntaxes: SyntaxSet,
-    pub stdout: Stdout,
+    pub writer: Mutex<W>,
     pub theme: Theme,
     pub is_truecolor: bool,
+    /// The layout of the current round, set by [`Terminal::print_round_info`].
+    pub layout: Mutex<Layout>,
 }
 
 impl Terminal {
+    /// Create a terminal that writes to stdout, or to a [`Capture`] if
+    /// [`CAPTURE_ENV`] is set.
     pub fn new() -> Result<Self> {
+        let writer: Box<dyn Write + Send> = match env::var_os(CAPTURE_ENV) {
+            Some(path) => Box::new(Capture {
+                buffer: Vec::new(),
+                path: path.into(),
+            }),
+            None => Box::new(stdout()),
+        };
+
+        Self::with_writer(writer)
+    }
+
+    /// Consume all ready-to-be-collected events to ensure that only future
+    /// ones are collected.
+    pub fn drain_events() -> Result<()> {
+        while event::poll(Duration::from_millis(1))? {
+            event::read()?;
+        }
+
+        Ok(())
+    }
+
+    /// Utility function to wait for a relevant char to be pressed. Events are
+    /// not drained first, so a key pressed while the round was being rendered
+    /// is still picked up (see [`Terminal::drain_events`]). With `--mouse`,
+    /// clicking an option in `layout` counts as pressing its key. Reaching
+    /// `deadline` counts as pressing q.
+    pub fn read_input_char(layout: Layout, deadline: Option<Instant>) -> Result<char> {
+        loop {
+            if !Self::poll_until(deadline)? {
+                return Ok('q');
+            }
+
+            match event::read()? {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char(char @ ('1'..='9' | 'q' | 'c' | 'w' | 'h' | 'p' | ' ')),
+                    modifiers,
+                    ..
+                }) => {
+                    if char == 'c' && modifiers != KeyModifiers::CONTROL {
+                        continue;
+                    }
+
+                    // Space pauses like p does.
+                    return Ok(if char == ' ' { 'p' } else { char });
+                }
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    row,
+                    ..
+                }) if ARGS.mouse => {
+                    if let Some(char) = Self::clicked_option(row, layout) {
+                        return Ok(char);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Wait until an event is ready to be read, returning false if `deadline`
+    /// is reached first. Without a deadline, this returns immediately.
+    pub fn poll_until(deadline: Option<Instant>) -> Result<bool> {
+        match deadline {
+            Some(deadline) => Ok(event::poll(
+                deadline.saturating_duration_since(Instant::now()),
+            )?),
+            None => Ok(true),
+        }
+    }
+
+    /// Get the key of the option that was clicked at `row`, if any. The
+    /// options are followed by the quit option.
+    #[must_use]
+    pub fn clicked_option(row: u16, layout: Layout) -> Option<char> {
+        let offset = usize::from(row.checked_sub(layout.option_row)?);
+
+        match offset.cmp(&layout.option_count) {
+            cmp::Ordering::Less => char::from_digit(offset as u32 + 1, 10),
+            cmp::Ordering::Equal => Some('q'),
+            cmp::Ordering::Greater => None,
+        }
+    }
+
+    /// Get the index of the option that a typed answer is, ignoring case and
+    /// surrounding whitespace.
+    #[must_use]
+    pub fn match_typed_answer(options: &[&str], answer: &str) -> Option<usize> {
+        options
+            .iter()
+            .position(|option| option.eq_ignore_ascii_case(answer.trim()))
+    }
+
+    /// Wait up to `timeout` for `key` to be pressed, returning whether it was.
+    /// Without a timeout, this waits for any key instead.
+    pub fn wait_for_key(key: char, timeout: Option<Duration>) -> Result<bool> {
+        Self::drain_events()?;
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if let Some(deadline) = deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() || !event::poll(remaining)? {
+                    return Ok(false);
+                }
+            }
+
+            if let Event::Key(KeyEvent { code, .. }) = event::read()? {
+                if code == KeyCode::Char(key) {
+                    return Ok(true);
+                }
+
+                if deadline.is_none() {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    /// Find a bundled theme by its name, ignoring case. If there's no such
+    /// theme, the names of the available ones are listed in the error.
+    pub fn find_theme<'t>(themes: &'t ThemeSet, name: &str) -> Result<&'t Theme> {
+        themes
+            .themes
+            .iter()
+            .find(|(other, _)| other.eq_ignore_ascii_case(name))
+            .map(|(_, theme)| theme)
+            .ok_or_else(|| {
+                let names: Vec<_> = themes.themes.keys().map(String::as_str).collect();
+                GuessError::Config(format!(
+                    "There's no theme named \"{name}\". The available themes are: {}",
+                    names.join(", ")
+                ))
+            })
+    }
+
+    /// Load the syntaxes bundled with the game.
+    pub fn load_syntaxes() -> Result<SyntaxSet> {
+        dumps::from_uncompressed_data(include_bytes!("../assets/dumps/syntaxes.dump"))
+            .map_err(|err| GuessError::Other(err.to_string()))
+    }
+
+    /// Get the name of a language's syntax, using [`SYNTAX_OVERRIDES`] if it
+    /// isn't the language's name.
+    #[must_use]
+    pub fn syntax_name(language: &str) -> &str {
+        SYNTAX_OVERRIDES
+            .iter()
+            .find(|(overridden, _)| *overridden == language)
+            .map_or(language, |(_, name)| name)
+    }
+
+    /// Get terminal width.
+    pub fn width() -> Result<usize> {
+        terminal::size()
+            .map(|(width, _)| width as usize)
+            .map_err(Into::into)
+    }
+
+    /// Get terminal height.
+    pub fn height() -> Result<usize> {
+        terminal::size()
+            .map(|(_, height)| height as usize)
+            .map_err(Into::into)
+    }
+
+    /// Get the number of rows of code that fit in the terminal with
+    /// `option_count` options. If that's less than `--max-lines`, it's
+    /// recorded in [`CLAMPED_LINES`] to be reported when the game ends.
+    pub fn max_code_rows(option_count: usize) -> Result<usize> {
+        // The hint row takes up a row like an option does.
+        let rows = Layout::max_code_rows(Self::height()?, option_count + usize::from(ARGS.hints));
+        if rows < usize::from(ARGS.max_lines) {
+            CLAMPED_LINES.fetch_min(rows, Ordering::Relaxed);
+        }
+
+        Ok(rows)
+    }
+}
+
+impl<W: Write + Send> Terminal<W> {
+    pub fn with_writer(mut writer: W) -> Result<Self> {
         #[cfg(windows)]
         let _ = enable_ansi_support();
 
-        let themes: ThemeSet = dumps::from_binary(include_bytes!("../assets/dumps/themes.dump"));
-        let syntaxes: SyntaxSet =
-            dumps::from_uncompressed_data(include_bytes!("../assets/dumps/syntaxes.dump"))?;
+        // Decoding the dumps can take a noticeable moment on slow machines.
+        if !cfg!(test) {
+            let _loading = execute!(writer, Print(&STRINGS.loading_syntaxes));
+        }
 
-        let mut stdout = stdout();
+        let themes: ThemeSet = dumps::from_binary(include_bytes!("../assets/dumps/themes.dump"));
+        let syntaxes = Terminal::load_syntaxes()?;
 
         if !cfg!(test) {
-            let _clear = execute!(stdout, EnterAlternateScreen, Hide, MoveTo(0, 0));
+            let _clear = execute!(
+                writer,
+                MoveToColumn(0),
+                Clear(ClearType::CurrentLine),
+                EnterAlternateScreen,
+                Hide,
+                MoveTo(0, 0)
+            );
             let _raw = enable_raw_mode();
+
+            if ARGS.mouse {
+                let _mouse = execute!(writer, EnableMouseCapture);
+            }
         }
 
         Ok(Self {
             syntaxes,
-            stdout,
-            theme: themes.themes[Self::get_theme()?].clone(),
+            writer: Mutex::new(writer),
+            theme: Self::get_theme(&themes)?,
             is_truecolor: Self::is_truecolor(),
+            layout: Mutex::new(Layout::default()),
         })
     }
 
-    /// Highlight a line of code.
-    pub fn highlight_line(&self, code: &str, highlighter: &mut HighlightLines) -> Option<String> {
-        let ranges = highlighter.highlight_line(code, &self.syntaxes).ok()?;
-        let mut colorized = Vec::with_capacity(ranges.len());
+    /// Lock the writer. This is used like [`std::io::Stdout::lock`] to make sure
+    /// output from different threads doesn't interleave.
+    pub fn lock(&self) -> Result<MutexGuard<'_, W>> {
+        self.writer.lock().map_err(Into::into)
+    }
+
+    /// Highlight a line of code, split into rows that are at most `row_width`
+    /// columns wide. If `strip_comments` is true, lines with comments are
+    /// excluded by returning [`None`], so the user can look at actual code.
+    pub fn highlight_line(
+        &self,
+        code: &str,
+        highlighter: &mut CodeHighlighter,
+        strip_comments: bool,
+        row_width: usize,
+    ) -> Option<Vec<CodeLine>> {
+        let has_comment = strip_comments && highlighter.has_comment(code, &self.syntaxes);
+        let ranges = highlighter
+            .highlighter
+            .highlight_line(code, &self.syntaxes)
+            .ok()?;
+
+        if has_comment {
+            return None;
+        }
+
+        // The pieces of each row, which are split wherever the next character
+        // wouldn't fit. Characters without a width (like the newline) never
+        // start a row.
+        let mut rows = vec![Vec::new()];
+        let mut column = 0;
 
         for (style, component) in ranges {
             let color = Self::to_ansi_color(style.foreground, self.is_truecolor);
+            let mut start = 0;
+
+            for (idx, char) in component.char_indices() {
+                let char_width = char.width().unwrap_or(0);
+                if char_width > 0 && column > 0 && column + char_width > row_width {
+                    if idx > start {
+                        rows.last_mut()
+                            .unwrap()
+                            .push((color, &component[start..idx]));
+                    }
+
+                    rows.push(Vec::new());
+                    start = idx;
+                    column = 0;
+                }
 
-            // This color represents comments. If the line includes a comment,
-            // it should be excluded from the output so the user can look at
-            // actual code. The second color is for bash specifically because it
-            // for some reason has a different comment color.
-            if matches!(color, Color::RGB(117, 113, 94) | Color::RGB(124, 120, 101)) {
-                return None;
-            };
+                column += char_width;
+            }
+
+            if start < component.len() {
+                rows.last_mut().unwrap().push((color, &component[start..]));
+            }
+        }
 
+        Some(
+            rows.iter()
+                .enumerate()
+                .map(|(idx, pieces)| Self::build_line(pieces, idx != 0))
+                .collect(),
+        )
+    }
+
+    /// Create a row of code from its highlighted pieces.
+    fn build_line(pieces: &[(Color, &str)], is_continuation: bool) -> CodeLine {
+        let mut raw = String::new();
+        let mut colorized = Vec::with_capacity(pieces.len());
+        let mut tokens = Vec::with_capacity(pieces.len());
+        let mut column = 0;
+
+        for &(color, component) in pieces {
+            raw += component;
             colorized.push(color.paint(component));
+
+            // Leading whitespace is skipped so that tokens don't overwrite
+            // anything, and trailing newlines so they don't move the cursor.
+            let token = component.trim_end_matches('\n');
+            let trimmed = token.trim_start();
+            if !trimmed.is_empty() {
+                let offset = token.width() - trimmed.width();
+                tokens.push((column + offset, color.paint(trimmed).to_string()));
+            }
+
+            column += component.width();
         }
 
-        Some(ANSIStrings(&colorized).to_string())
+        CodeLine {
+            raw,
+            highlighted: ANSIStrings(&colorized).to_string(),
+            tokens,
+            is_continuation,
+        }
     }
 
     /// Converts [`syntect::highlighting::Color`] to [`ansi_term::Color`]. The
@@ -146,75 +807,167 @@ impl Terminal {
             .unwrap_or_default()
     }
 
-    /// Get light/dark mode specific theme.
-    pub fn get_theme() -> Result<&'static str> {
-        if let Ok(theme) = ThemeStyle::try_from(ARGS.theme.clone()) {
+    /// Get the theme from `--theme-file`, a theme named by `--theme`, or the
+    /// light/dark mode specific theme, in that order. Whichever is given is
+    /// stored in the config so it's used in later games too. With
+    /// `--pin-theme`, the resolved light/dark theme is stored so it stays the
+    /// same even if the system switches between dark and light mode.
+    pub fn get_theme(themes: &ThemeSet) -> Result<Theme> {
+        if ARGS.theme_file.is_some() && ARGS.theme.is_some() {
+            return Err(GuessError::Config(String::from(
+                "--theme-file can't be used with --theme",
+            )));
+        }
+
+        let (mut style, name, file) = if let Some(path) = &ARGS.theme_file {
+            (None, None, Some(PathBuf::from(path)))
+        } else {
+            match ARGS.theme.as_deref() {
+                // Forget the stored theme so it's detected on every launch.
+                Some("auto") => (None, None, None),
+                Some(theme) => match ThemeStyle::try_from(Some(theme.to_string())) {
+                    Ok(style) => (Some(style), None, None),
+                    Err(()) => (None, Some(theme.to_string()), None),
+                },
+                None => (
+                    CONFIG.theme.clone(),
+                    CONFIG.theme_name.clone(),
+                    CONFIG.theme_file.clone(),
+                ),
+            }
+        };
+
+        // The theme is resolved before anything is stored, so that a theme
+        // that can't be found isn't remembered.
+        let theme = match (&file, &name) {
+            (Some(path), _) => ThemeSet::get_theme(path).map_err(|err| {
+                GuessError::Config(format!(
+                    "Couldn't load the theme file {}: {err}",
+                    path.display()
+                ))
+            })?,
+            (None, Some(name)) => Terminal::find_theme(themes, name)?.clone(),
+            (None, None) => {
+                let resolved = style.clone().unwrap_or_else(Self::detect_theme);
+                if ARGS.pin_theme {
+                    style = Some(resolved.clone());
+                }
+
+                Terminal::find_theme(themes, resolved.into())?.clone()
+            }
+        };
+
+        if ARGS.theme.is_some() || ARGS.theme_file.is_some() || ARGS.pin_theme {
+            // The file is stored as an absolute path so it can be found from
+            // any directory.
+            let file = file.map(|path| fs::canonicalize(&path).unwrap_or(path));
             confy::store(
                 "guess-that-lang",
                 Config {
-                    theme: Some(theme.clone()),
-                    ..CONFIG.clone()
+                    theme: style,
+                    theme_name: name,
+                    theme_file: file,
+                    ..confy::load("guess-that-lang")?
                 },
             )?;
+        }
 
-            Ok(theme.into())
-        } else if let Some(theme) = CONFIG.theme.clone() {
-            Ok(theme.into())
-        } else {
-            #[cfg(target_os = "macos")]
-            {
-                if !macos_dark_mode_active() {
-                    return Ok(ThemeStyle::Light.into());
-                }
-            }
+        Ok(theme)
+    }
+
+    /// Detect whether the system is using dark or light mode. This defaults to
+    /// dark mode on unsupported platforms.
+    #[must_use]
+    pub fn detect_theme() -> ThemeStyle {
+        #[cfg(target_os = "macos")]
+        let is_dark = macos_dark_mode_active();
+        #[cfg(target_os = "linux")]
+        let is_dark = linux_dark_mode_active();
+        #[cfg(windows)]
+        let is_dark = windows_dark_mode_active();
+        #[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+        let is_dark = true;
 
-            Ok(ThemeStyle::Dark.into())
+        if is_dark {
+            ThemeStyle::Dark
+        } else {
+            ThemeStyle::Light
         }
     }
 
     /// Parses the code in a number of ways:
     /// - Cuts the code off after in exceeds the terminal width, replacing the
-    ///   last three characters with "..."
-    /// - Cuts out all comments
-    /// - Cuts the code off after 10 non-empty lines
+    ///   last characters with the truncation marker ("..." by default), or
+    ///   wraps it onto more rows with `--wrap`
+    /// - Cuts out all comments, unless `strip_comments` is false
+    /// - Cuts the code off after `--max-lines` non-empty lines, or after
+    ///   `max_rows` lines in total if there's no room for that many
     /// - Removes all but the first of all consecutive newlines
     /// - Trims leading and trailing newlines
     pub fn parse_code(
         &self,
         code: &str,
-        mut highlighter: HighlightLines,
+        mut highlighter: CodeHighlighter,
         width: &usize,
-    ) -> Option<Vec<(String, String)>> {
-        let mut taken_lines: u8 = 0;
+        max_rows: usize,
+        strip_comments: bool,
+    ) -> Option<Vec<CodeLine>> {
+        let mut taken_lines: usize = 0;
 
         let mut lines: Vec<_> = LinesWithEndings::from(code)
             .filter_map(move |line| {
-                let trimmed = if line.len() + 9 > *width {
-                    format!("{}...", &line[..*width - 12])
+                // Lines are measured in columns rather than bytes, since
+                // characters can be more than one byte and one column wide.
+                let line_width = line.trim_end_matches(['\r', '\n']).width();
+
+                if ARGS.wrap {
+                    let row_width = width.saturating_sub(Layout::CODE_COLUMN);
+                    self.highlight_line(line, &mut highlighter, strip_comments, row_width)
+                } else if line_width + Layout::CODE_COLUMN > *width {
+                    let marker = &ARGS.truncation_marker;
+                    let trimmed = truncate_to_width(
+                        line,
+                        width.saturating_sub(Layout::CODE_COLUMN + marker.width()),
+                    );
+
+                    // The marker is colored separately so it can't be confused
+                    // with code.
+                    self.highlight_line(trimmed, &mut highlighter, strip_comments, usize::MAX)
+                        .map(|mut rows| {
+                            let marker = marker.as_str().dark_grey().italic().to_string();
+
+                            // Lines aren't split into rows without `--wrap`.
+                            if let Some(line) = rows.first_mut() {
+                                line.raw = format!("{trimmed}{}", ARGS.truncation_marker);
+                                line.highlighted += &marker;
+                                line.tokens.push((trimmed.width(), marker));
+                            }
+
+                            rows
+                        })
                 } else {
-                    line.to_owned()
-                };
-
-                self.highlight_line(&trimmed, &mut highlighter)
-                    .map(|highlighted| (trimmed, highlighted))
+                    self.highlight_line(line, &mut highlighter, strip_comments, usize::MAX)
+                }
             })
-            .take_while(move |(line, _)| {
-                if line == "\n" {
+            .flatten()
+            .take_while(move |line| {
+                // Wrapped rows are part of the line before them.
+                if line.is_blank() || line.is_continuation {
                     true
                 } else {
                     taken_lines += 1;
-                    taken_lines <= 10
+                    taken_lines <= usize::from(ARGS.max_lines)
                 }
             })
             .collect();
 
-        lines.dedup_by(|(a, _), (b, _)| a == "\n" && b == "\n");
+        lines.dedup_by(|a, b| a.is_blank() && b.is_blank());
 
         let count_end = lines.len()
             - lines
                 .iter()
                 .rev()
-                .take_while(|&(line, _)| line == "\n")
+                .take_while(|line| line.is_blank())
                 .count();
 
         lines.truncate(count_end);
@@ -223,7 +976,7 @@ impl Terminal {
             return None;
         }
 
-        let count_start = lines.iter().take_while(|&(line, _)| line == "\n").count();
+        let count_start = lines.iter().take_while(|line| line.is_blank()).count();
 
         if count_start != 0 {
             for i in count_start..lines.len() {
@@ -233,32 +986,78 @@ impl Terminal {
             lines.truncate(lines.len() - count_start);
         }
 
-        Some(lines)
+        // Blank lines take up rows too, so they count towards `max_rows`. A
+        // wrapped line is kept whole or not at all.
+        if lines.len() > max_rows {
+            let mut end = max_rows;
+            while end > 0 && lines[end].is_continuation {
+                end -= 1;
+            }
+
+            lines.truncate(end);
+        }
+
+        while lines.last().map_or(false, CodeLine::is_blank) {
+            lines.pop();
+        }
+
+        (!lines.is_empty()).then_some(lines)
+    }
+
+    /// Highlight all of `code` for `--review`, without leaving out comments
+    /// or cutting off lines that don't fit.
+    #[must_use]
+    pub fn highlight_code(&self, code: &str, mut highlighter: CodeHighlighter) -> Vec<CodeLine> {
+        LinesWithEndings::from(code.trim_end())
+            .filter_map(|line| self.highlight_line(line, &mut highlighter, false, usize::MAX))
+            .flatten()
+            .collect()
     }
 
     /// Print the base table and all elements inside, including the code in dot form.
     pub fn print_round_info(
         &self,
         options: &[&str],
-        code_lines: &[(String, String)],
+        code_lines: &[CodeLine],
         width: &usize,
         total_points: u32,
+        accuracy: Option<Accuracy>,
+        multiplier: Option<f32>,
     ) -> Result<()> {
         let pipe = "│".white().dim();
 
+        let accuracy_text = accuracy.map_or_else(String::new, Self::format_accuracy);
+
+        // The high score doesn't change during a round, so the multiplier can
+        // go after it.
+        let multiplier_text = multiplier.map_or_else(String::new, |multiplier| {
+            format!(
+                "    {} {}",
+                STRINGS.streak_bonus.as_str().bold(),
+                scoring::format_multiplier(multiplier).yellow()
+            )
+        });
+
+        let wager_text = if ARGS.wager {
+            Self::format_wager(0)
+        } else {
+            String::new()
+        };
+
         let points = format!(
-            "{padding}{pipe} {}{}\r\n{padding}{pipe} {}{}\r\n{padding}{pipe} {}{}",
-            "High Score: ".bold(),
-            CONFIG.high_score.to_string().magenta(),
-            "Total Points: ".bold(),
-            total_points.to_string().cyan(),
-            "Available Points: ".bold(),
-            Color::RGB(0, 255, 0).paint("100"),
-            padding = " ".repeat(7),
+            "{padding}{pipe} {}{}{multiplier_text}{wager_text}\r\n{padding}{pipe} {}{}\r\n{padding}{pipe} {}{}{accuracy_text}{}",
+            format!("{} ", STRINGS.high_score).bold(),
+            format_number(CONFIG.high_score).magenta(),
+            format!("{} ", STRINGS.total_points).bold(),
+            format_number(total_points).cyan(),
+            format!("{} ", STRINGS.available_points).bold(),
+            Self::paint_points(Difficulty::current().starting_points()),
+            Self::format_hint_row(),
+            padding = " ".repeat(Layout::GUTTER_WIDTH),
         );
 
-        let line_separator_start = "─".repeat(7);
-        let line_separator_end = "─".repeat(width - 8);
+        let line_separator_start = "─".repeat(Layout::GUTTER_WIDTH);
+        let line_separator_end = "─".repeat(width.saturating_sub(Layout::GUTTER_WIDTH + 1));
 
         let [top, mid, bottom] = ["┬", "┼", "┴"].map(|char| {
             (line_separator_start.clone() + char + &line_separator_end)
@@ -266,117 +1065,1052 @@ impl Terminal {
                 .dim()
         });
 
+        let show_costs = Self::shows_line_costs();
         let dotted_code = code_lines
             .iter()
-            .enumerate()
-            .map(|(idx, (line, _))| {
+            .zip(Self::line_numbers(code_lines))
+            .map(|(line, number)| {
                 let dots: String = line
+                    .raw
                     .chars()
-                    // Replace all non whitespace characters with dots.
-                    .map(|char| if char.is_whitespace() { char } else { '·' })
+                    // Replace all non whitespace characters with a dot for
+                    // each column they take up.
+                    .map(|char| {
+                        if char.is_whitespace() {
+                            char.to_string()
+                        } else {
+                            "·".repeat(char.width().unwrap_or(0))
+                        }
+                    })
                     .collect();
 
+                let gutter = Self::format_gutter(number, show_costs && !line.is_blank());
+
                 // Trim the end of the line to remove extraneous newlines, and
                 // then add one manually.
-                format!("{: ^7}{pipe} {}\r\n", idx + 1, dots.trim_end())
+                format!("{gutter}{pipe} {}\r\n", dots.trim_end())
             })
             .collect::<String>();
 
-        let option_text = options
-            .iter()
-            .enumerate()
-            .map(|(idx, option)| Self::format_option(&(idx + 1).to_string(), option))
-            .collect::<Vec<_>>()
-            .join("\r\n");
+        // With expert, the options are replaced by a row to type the answer
+        // in and a blank row for the correct answer.
+        let is_typed = Difficulty::current().is_typed();
+        let option_rows = if is_typed {
+            Difficulty::Expert.option_count()
+        } else {
+            options.len()
+        };
+
+        let option_text = if is_typed {
+            format!("{}\r\n", Self::format_option(">", ""))
+        } else {
+            options
+                .iter()
+                .enumerate()
+                .map(|(idx, option)| Self::format_option(&(idx + 1).to_string(), option))
+                .collect::<Vec<_>>()
+                .join("\r\n")
+        };
 
-        let quit_option_text = Self::format_option("q", "Quit");
+        let quit_option_text = if is_typed {
+            Self::format_option("esc", &STRINGS.quit)
+        } else {
+            Self::format_option("q", &STRINGS.quit)
+        };
+
+        let table = format!("{top}\r\n{points}\r\n{mid}\r\n{dotted_code}{bottom}");
+        let prompt = format!(
+            "{}\r\n\r\n{option_text}\r\n{quit_option_text}",
+            if is_typed {
+                &STRINGS.typed_prompt
+            } else if ARGS.mode == GameMode::Category {
+                &STRINGS.category_prompt
+            } else {
+                &STRINGS.prompt
+            }
+        );
+
+        // [`Layout::new`] depends on this order.
+        let text = if ARGS.options_first {
+            format!("{prompt}\r\n\r\n{table}")
+        } else {
+            format!("{table}\r\n\r\n{prompt}")
+        };
 
-        let text = format!(
-            "{top}\r\n{points}\r\n{mid}\r\n{dotted_code}{bottom}\r\n\r\n{PROMPT}\r\n\r\n{option_text}\r\n{quit_option_text}"
+        *self.layout.lock()? = Layout::new(
+            code_lines.len(),
+            option_rows,
+            ARGS.options_first,
+            ARGS.hints,
         );
 
-        execute!(self.stdout.lock(), Print(text)).map_err(Into::into)
+        let mut writer = self.lock()?;
+        execute!(writer, Print(text)).map_err(Into::into)
     }
 
-    pub fn get_highlighter(&self, language: &str) -> HighlightLines {
+    /// Get the layout of the current round.
+    pub fn layout(&self) -> Result<Layout> {
+        Ok(*self.layout.lock()?)
+    }
+
+    /// Whether `--show-points-per-line` applies, which is only when code is
+    /// revealed a line at a time.
+    #[must_use]
+    pub fn shows_line_costs() -> bool {
+        ARGS.show_points_per_line && ARGS.study_time.is_none() && ARGS.reveal != RevealMode::Tokens
+    }
+
+    /// Get the number of the line each row of code is on. Rows that continue a
+    /// wrapped line have no number of their own.
+    #[must_use]
+    pub fn line_numbers(code_lines: &[CodeLine]) -> Vec<Option<usize>> {
+        let mut number = 0;
+        code_lines
+            .iter()
+            .map(|line| {
+                (!line.is_continuation).then(|| {
+                    number += 1;
+                    number
+                })
+            })
+            .collect()
+    }
+
+    /// Format the gutter of a row of code, which holds its line number,
+    /// followed by what revealing it costs with `show_cost`. The gutter is
+    /// empty for rows without a line number (see [`Terminal::line_numbers`]).
+    #[must_use]
+    pub fn format_gutter(line_number: Option<usize>, show_cost: bool) -> String {
+        let width = Layout::GUTTER_WIDTH;
+        let line_number = match line_number {
+            Some(line_number) => line_number,
+            None => return " ".repeat(width),
+        };
+
+        if show_cost {
+            let cost = format!("-{}", Difficulty::current().points_per_step());
+            format!(
+                "{line_number:>number_width$} {}",
+                cost.as_str().dark_grey(),
+                number_width = width.saturating_sub(cost.len() + 1)
+            )
+        } else {
+            format!("{line_number:^width$}")
+        }
+    }
+
+    /// Move the cursor to the start of the row of the option at `idx`.
+    pub fn queue_move_to_option(stdout: &mut W, layout: Layout, idx: usize) -> Result<()> {
+        queue!(stdout, MoveTo(0, layout.option_row + idx as u16)).map_err(Into::into)
+    }
+
+    pub fn get_highlighter(&self, language: &str) -> CodeHighlighter<'_> {
         let syntax = self
-            .syntaxes
-            .find_syntax_by_name(language)
+            .find_language_syntax(language)
             .unwrap_or_else(|| self.syntaxes.find_syntax_plain_text());
 
-        HighlightLines::new(syntax, &self.theme)
+        CodeHighlighter::new(syntax, &self.theme)
+    }
+
+    /// Find the syntax for a language, using [`SYNTAX_OVERRIDES`] if its name
+    /// isn't the syntax's name.
+    #[must_use]
+    pub fn find_language_syntax(&self, language: &str) -> Option<&SyntaxReference> {
+        self.syntaxes
+            .find_syntax_by_name(Terminal::syntax_name(language))
+    }
+
+    /// Get a highlighter for fetched code, using [`Terminal::find_code_syntax`].
+    pub fn get_code_highlighter(&self, data: &CodeData) -> CodeHighlighter<'_> {
+        CodeHighlighter::new(self.find_code_syntax(data), &self.theme)
+    }
+
+    /// Find the syntax for fetched code. If its language has no syntax, the
+    /// syntax is picked from its `Content-Type` or its first line (e.g. a
+    /// shebang), before falling back to plain text.
+    pub fn find_code_syntax(&self, data: &CodeData) -> &SyntaxReference {
+        self.find_language_syntax(&data.language)
+            .or_else(|| {
+                let content_type = data.content_type.as_deref()?;
+                let (_, name) = MIME_SYNTAXES
+                    .iter()
+                    .find(|(mime, _)| mime.eq_ignore_ascii_case(content_type))?;
+
+                self.syntaxes.find_syntax_by_name(name)
+            })
+            .or_else(|| {
+                self.syntaxes
+                    .find_syntax_by_first_line(data.code.lines().next()?)
+            })
+            .unwrap_or_else(|| self.syntaxes.find_syntax_plain_text())
+    }
+
+    /// Count the distinct keywords and operators in parsed code, which hint
+    /// at its language. Snippets with few of them (e.g. only braces and
+    /// identifiers) are too ambiguous to guess.
+    #[must_use]
+    pub fn count_distinct_tokens(
+        &self,
+        code_lines: &[CodeLine],
+        syntax: &SyntaxReference,
+    ) -> usize {
+        let distinctive = [
+            Scope::new("keyword").unwrap(),
+            Scope::new("storage").unwrap(),
+        ];
+        let mut state = ParseState::new(syntax);
+        let mut stack = ScopeStack::new();
+        let mut tokens = HashSet::new();
+
+        for line in code_lines {
+            let ops = match state.parse_line(&line.raw, &self.syntaxes) {
+                Ok(ops) => ops,
+                Err(_) => continue,
+            };
+
+            for (range, op) in ScopeRangeIterator::new(&ops, &line.raw) {
+                if stack.apply(op).is_err() {
+                    break;
+                }
+
+                let token = line.raw[range].trim();
+                let is_distinctive = stack
+                    .as_slice()
+                    .iter()
+                    .any(|scope| distinctive.iter().any(|prefix| prefix.is_prefix_of(*scope)));
+
+                if !token.is_empty() && is_distinctive {
+                    tokens.insert(token);
+                }
+            }
+        }
+
+        tokens.len()
     }
 
     /// Create a loop that will reveal a line of code and decrease
-    /// `available_points` every 1.5 seconds.
+    /// `available_points` every `--reveal-interval` ms. The points decrease by
+    /// the same amount for each step no matter how long it is. The order is
+    /// shuffled with the game's display `rng`.
     pub fn start_showing_code(
         &self,
-        code_lines: &[(String, String)],
+        code_lines: &[CodeLine],
         available_points: &Mutex<f32>,
-        receiver: Receiver<()>,
+        is_revealed: &AtomicBool,
+        receiver: Receiver<RevealSignal>,
+        rng: &SharedRng,
     ) -> Result<()> {
-        let mut code_lines: Vec<_> = code_lines.iter().enumerate().collect();
+        let steps = Self::get_reveal_steps(
+            code_lines,
+            ARGS.reveal,
+            ARGS.shuffle,
+            &mut random::lock(rng),
+        );
+        let line_numbers = Self::line_numbers(code_lines);
+        let layout = self.layout()?;
+
+        // Consume receiver.
+        let receiver = receiver;
+
+        for (idx, step) in steps.into_iter().enumerate() {
+            let millis = if idx == 0 {
+                ARGS.wait()
+            } else {
+                ARGS.reveal_interval
+                    .unwrap_or_else(|| Difficulty::current().reveal_interval())
+            };
+
+            // The receiver will be notified when the user has selected an
+            // option, at which point the code should not be updated further.
+            if self.wait_for_step(&receiver, Duration::from_millis(millis), layout)? {
+                break;
+            }
+
+            let mut stdout = self.lock()?;
+
+            queue!(stdout, SavePosition)?;
+            Self::queue_reveal_step(&mut stdout, layout, &line_numbers, &step)?;
+
+            // `available_points` should not be decreased on the first step.
+            if idx != 0 {
+                let mut available_points = available_points.lock()?;
+                // Hints may have already taken most of the points.
+                *available_points =
+                    (*available_points - Difficulty::current().points_per_step()).max(0.0);
+
+                queue!(
+                    stdout,
+                    layout.points_position(2, &STRINGS.available_points),
+                    Print(format!("{} ", Self::paint_points(*available_points)))
+                )?;
+            }
+
+            execute!(stdout, RestorePosition)?;
+            is_revealed.store(true, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+
+    /// Wait `duration` before the next step of revealing code, returning true
+    /// if the round was answered in the meantime. While paused, the wait is
+    /// frozen, and only the time that was left before pausing is waited once
+    /// it's resumed.
+    pub fn wait_for_step(
+        &self,
+        receiver: &Receiver<RevealSignal>,
+        duration: Duration,
+        layout: Layout,
+    ) -> Result<bool> {
+        let mut remaining = duration;
+
+        loop {
+            let started_at = Instant::now();
+            match receiver.recv_timeout(remaining) {
+                Err(RecvTimeoutError::Timeout) => return Ok(false),
+                // The input thread stops sending once it's done, e.g. because
+                // of an error.
+                Ok(RevealSignal::Answered) | Err(RecvTimeoutError::Disconnected) => {
+                    return Ok(true)
+                }
+                Ok(RevealSignal::TogglePause) => {
+                    remaining = remaining.saturating_sub(started_at.elapsed());
+                    self.print_paused(layout, true)?;
+
+                    if receiver.recv() != Ok(RevealSignal::TogglePause) {
+                        return Ok(true);
+                    }
+
+                    self.print_paused(layout, false)?;
+                }
+            }
+        }
+    }
 
-        if ARGS.shuffle {
-            code_lines.shuffle(&mut thread_rng());
+    /// Show the `hints` taken so far in the hint row, and the
+    /// `available_points` that are left after paying for them.
+    pub fn print_hints(&self, hints: &[String], available_points: f32) -> Result<()> {
+        let layout = self.layout()?;
+
+        let mut stdout = self.lock()?;
+        execute!(
+            stdout,
+            SavePosition,
+            layout.points_position(2, &STRINGS.available_points),
+            Print(format!("{} ", Self::paint_points(available_points))),
+            layout.points_position(3, &STRINGS.hint),
+            Clear(ClearType::UntilNewLine),
+            Print(hints.join("   ").yellow()),
+            RestorePosition
+        )
+        .map_err(Into::into)
+    }
+
+    /// Show or clear the indicator that revealing code is paused, at the end
+    /// of the row with the total points.
+    pub fn print_paused(&self, layout: Layout, is_paused: bool) -> Result<()> {
+        let label = STRINGS.paused.as_str();
+        let column = Terminal::width()?.saturating_sub(label.width() + 1) as u16;
+        let text = if is_paused {
+            label.yellow().bold().to_string()
+        } else {
+            " ".repeat(label.width())
         };
 
-        // This has to be made a variable as opposed to just checking if idx ==
-        // 0 because the lines could be shuffled.
-        let mut is_first_line = true;
+        let mut stdout = self.lock()?;
+        execute!(
+            stdout,
+            SavePosition,
+            MoveTo(column, layout.points_row(1)),
+            Print(text),
+            RestorePosition
+        )
+        .map_err(Into::into)
+    }
+
+    /// Show the time left until `deadline` at the end of the row with the high
+    /// score every second, until it's reached or `receiver` is disconnected
+    /// once the round is over. This does nothing without a deadline, i.e.
+    /// outside of blitz mode.
+    pub fn start_blitz_timer(
+        &self,
+        deadline: Option<Instant>,
+        receiver: Receiver<()>,
+    ) -> Result<()> {
+        let deadline = match deadline {
+            Some(deadline) => deadline,
+            None => return Ok(()),
+        };
 
         // Consume receiver.
         let receiver = receiver;
 
-        for (idx, (raw, line)) in code_lines {
-            if raw == "\n" {
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            self.print_time_left(remaining)?;
+
+            if remaining.is_zero() {
+                return Ok(());
+            }
+
+            // Wait until the shown time changes, which is when one less whole
+            // second is left.
+            let wait = remaining.saturating_sub(Duration::from_secs(ceil_seconds(remaining) - 1));
+            if receiver.recv_timeout(wait) != Err(RecvTimeoutError::Timeout) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Show the time left in blitz mode at the end of the row with the high
+    /// score.
+    pub fn print_time_left(&self, remaining: Duration) -> Result<()> {
+        let text = fill(&STRINGS.time_left, format_duration(remaining));
+        let column = Terminal::width()?.saturating_sub(text.width() + 1) as u16;
+        let layout = self.layout()?;
+
+        let mut stdout = self.lock()?;
+        execute!(
+            stdout,
+            SavePosition,
+            MoveTo(column, layout.points_row(0)),
+            Print(text.yellow().bold()),
+            RestorePosition
+        )
+        .map_err(Into::into)
+    }
+
+    /// Replace the prompt with the one for a bonus round with
+    /// `--bonus-rounds`.
+    pub fn print_bonus_prompt(&self) -> Result<()> {
+        let layout = self.layout()?;
+
+        let mut stdout = self.lock()?;
+        execute!(
+            stdout,
+            SavePosition,
+            MoveTo(0, layout.prompt_row()),
+            Clear(ClearType::CurrentLine),
+            Print(STRINGS.bonus_prompt.as_str().bold()),
+            RestorePosition
+        )
+        .map_err(Into::into)
+    }
+
+    /// Show a notice at the end of the quit option's row if code is played
+    /// from a [`Fallback`] because it can't be fetched, or otherwise the
+    /// number of Github requests left before being ratelimited, if it's known.
+    pub fn print_footer(&self) -> Result<()> {
+        let remaining = RATELIMIT_REMAINING.load(Ordering::Relaxed);
+        let (text, is_notice) = match cache::fallback() {
+            Some(Fallback::Cache) => (STRINGS.cache_fallback.clone(), true),
+            Some(Fallback::Offline) => (STRINGS.offline_fallback.clone(), true),
+            None if remaining != u32::MAX => (
+                fill(&STRINGS.requests_left, format_number(remaining)),
+                false,
+            ),
+            None => return Ok(()),
+        };
+
+        let column = Terminal::width()?.saturating_sub(text.width() + 1) as u16;
+        let text = if is_notice {
+            text.yellow()
+        } else {
+            text.dark_grey()
+        };
+        let layout = self.layout()?;
+
+        let mut stdout = self.lock()?;
+        execute!(
+            stdout,
+            SavePosition,
+            MoveTo(column, layout.quit_row()),
+            Print(text),
+            RestorePosition
+        )
+        .map_err(Into::into)
+    }
+
+    /// Show the `lives` left with `--lives` at the end of the row with the
+    /// available points, as hearts followed by a ✗ for each one that was lost.
+    pub fn print_lives(&self, lives: u32) -> Result<()> {
+        let max = ARGS.lives.unwrap_or(lives);
+        let (hearts, lost) = format_lives(lives, max);
+        let text = format!("{} {hearts}{lost}", STRINGS.lives);
+        let column = Terminal::width()?.saturating_sub(text.width() + 1) as u16;
+        let layout = self.layout()?;
+
+        let mut stdout = self.lock()?;
+        execute!(
+            stdout,
+            SavePosition,
+            MoveTo(column, layout.points_row(2)),
+            Print(STRINGS.lives.as_str().bold()),
+            Print(format!(" {}", hearts.red())),
+            Print(lost.dark_grey()),
+            RestorePosition
+        )
+        .map_err(Into::into)
+    }
+
+    /// Move to the position of each piece of dotted code in a step from
+    /// [`Terminal::get_reveal_steps`] and replace it with the real code. The
+    /// cost of revealing a line is cleared along with it, using the
+    /// `line_numbers` of every row.
+    pub fn queue_reveal_step(
+        stdout: &mut W,
+        layout: Layout,
+        line_numbers: &[Option<usize>],
+        step: &[(usize, usize, &str)],
+    ) -> Result<()> {
+        for (row, column, text) in step {
+            if Self::shows_line_costs() {
+                queue!(
+                    stdout,
+                    MoveTo(0, layout.code_row + *row as u16),
+                    Print(Self::format_gutter(line_numbers[*row], false))
+                )?;
+            }
+
+            queue!(stdout, layout.code_position(*row, *column), Print(text))?;
+        }
+
+        Ok(())
+    }
+
+    /// Split the code into the pieces that are revealed together in
+    /// [`Terminal::start_showing_code`], as (row, column, text). There is one
+    /// step per non-empty line in every mode, so points decay at the same rate
+    /// in every [`RevealMode`]. The rows of a wrapped line are revealed
+    /// together. `shuffle` only applies to [`RevealMode::Lines`]. Lines and
+    /// tokens are shuffled with `rng`.
+    #[must_use]
+    pub fn get_reveal_steps<'a>(
+        code_lines: &'a [CodeLine],
+        mode: RevealMode,
+        shuffle: bool,
+        rng: &mut StdRng,
+    ) -> Vec<Vec<(usize, usize, &'a str)>> {
+        let mut lines: Vec<Vec<(usize, &CodeLine)>> = Vec::new();
+        for (row, line) in code_lines.iter().enumerate() {
+            match lines.last_mut() {
+                Some(rows) if line.is_continuation => rows.push((row, line)),
+                _ if line.is_blank() => {}
+                _ => lines.push(vec![(row, line)]),
+            }
+        }
+
+        let to_steps = |lines: Vec<Vec<(usize, &'a CodeLine)>>| {
+            lines
+                .into_iter()
+                .map(|rows| {
+                    rows.into_iter()
+                        .map(|(row, line)| (row, 0, line.highlighted.as_str()))
+                        .collect()
+                })
+                .collect()
+        };
+
+        match mode {
+            RevealMode::Lines => {
+                if shuffle {
+                    lines.shuffle(rng);
+                }
+
+                to_steps(lines)
+            }
+            // Only the order of the lines is reversed, so the rows they're
+            // revealed at stay the same.
+            RevealMode::BottomUp => {
+                lines.reverse();
+                to_steps(lines)
+            }
+            RevealMode::Tokens => {
+                let mut tokens: Vec<_> = lines
+                    .iter()
+                    .flatten()
+                    .flat_map(|(row, line)| {
+                        line.tokens
+                            .iter()
+                            .map(move |(column, token)| (*row, *column, token.as_str()))
+                    })
+                    .collect();
+
+                tokens.shuffle(rng);
+
+                let steps = lines.len().max(1);
+                let per_step = (tokens.len() + steps - 1) / steps;
+                tokens.chunks(per_step.max(1)).map(<[_]>::to_vec).collect()
+            }
+        }
+    }
+
+    /// Reveal the lines of code one by one without touching any points. This
+    /// is used to replay a round.
+    pub fn replay_code(&self, code_lines: &[CodeLine]) -> Result<()> {
+        let layout = self.layout()?;
+        let line_numbers = Self::line_numbers(code_lines);
+        for (idx, (line, number)) in code_lines.iter().zip(line_numbers).enumerate() {
+            if line.is_blank() {
                 continue;
             }
 
-            let millis = if is_first_line { ARGS.wait } else { 1500 };
-            is_first_line = false;
+            // The rows of a wrapped line are shown together.
+            if !line.is_continuation {
+                thread::sleep(Duration::from_millis(500));
+            }
 
-            // The receiver will be notified when the user has selected an
-            // option, at which point the code should not be updated further.
-            if receiver.recv_timeout(Duration::from_millis(millis)).is_ok() {
-                break;
+            let mut stdout = self.lock()?;
+            execute!(
+                stdout,
+                SavePosition,
+                MoveTo(0, layout.code_row + idx as u16),
+                Print(Self::format_gutter(number, false)),
+                layout.code_position(idx, 0),
+                Print(&line.highlighted),
+                RestorePosition
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Warn that preloading has been paused because the ratelimit is nearly
+    /// exhausted.
+    pub fn print_ratelimit_warning(&self, remaining: u32) -> Result<()> {
+        let mut stdout = self.lock()?;
+        let warning = fill(&STRINGS.ratelimit_warning, format_number(remaining));
+
+        execute!(stdout, Print(format!("\r\n\r\n{}", warning.yellow()))).map_err(Into::into)
+    }
+
+    /// Show how many rounds have been fetched by [`Game::prefetch`].
+    pub fn print_prefetch_progress(&self, fetched: u32, total: u32) -> Result<()> {
+        let mut stdout = self.lock()?;
+        execute!(
+            stdout,
+            MoveTo(0, 0),
+            Clear(ClearType::CurrentLine),
+            Print(fill(
+                &STRINGS.fetching_rounds,
+                format!("{fetched}/{total}").cyan()
+            ))
+        )
+        .map_err(Into::into)
+    }
+
+    /// Format all of the code of a round for `--review`, with the answer
+    /// above it and where it's from below it.
+    #[must_use]
+    pub fn format_review(round: &Round) -> String {
+        let pipe = "│".white().dim();
+        let code: String = round
+            .full_code
+            .iter()
+            .zip(Self::line_numbers(&round.full_code))
+            .map(|(line, number)| {
+                format!(
+                    "{}{pipe} {}\r\n",
+                    Self::format_gutter(number, false),
+                    line.highlighted
+                )
+            })
+            .collect();
+
+        let source = round.source.as_ref().map_or_else(String::new, |source| {
+            format!(
+                "{}\r\n",
+                fill(&STRINGS.review_source, source.as_str().cyan())
+            )
+        });
+
+        format!(
+            "{}\r\n\r\n{code}\r\n{source}{}",
+            round.language.as_str().bold(),
+            STRINGS.review_continue.as_str().dark_grey()
+        )
+    }
+
+    /// Show all of the code of a round that was just answered, and wait for
+    /// any key to be pressed.
+    pub fn print_review(&self, round: &Round) -> Result<()> {
+        let mut stdout = self.lock()?;
+        execute!(
+            stdout,
+            Clear(ClearType::All),
+            MoveTo(0, 0),
+            Print(Self::format_review(round))
+        )?;
+        drop(stdout);
+
+        Terminal::wait_for_key(' ', None)?;
+        Ok(())
+    }
+
+    /// Print a summary of the game once it's over: the score, the rounds that
+    /// were answered correctly for each language, a sparkline of the points
+    /// won in each round, and whether `high_score` was beaten.
+    pub fn print_summary(
+        &self,
+        points: u32,
+        high_score: u32,
+        records: &[RoundRecord],
+    ) -> Result<()> {
+        let mut lines = vec![
+            String::new(),
+            fill(&STRINGS.scored, format_number(points).green().bold()),
+        ];
+
+        if !records.is_empty() {
+            let mut languages: BTreeMap<&str, Accuracy> = BTreeMap::new();
+            for record in records {
+                languages
+                    .entry(&record.language)
+                    .or_default()
+                    .record(record.was_correct);
+            }
+
+            let correct = languages
+                .values()
+                .map(|accuracy| accuracy.correct)
+                .sum::<u32>();
+            let width = languages
+                .keys()
+                .map(|language| language.width())
+                .max()
+                .unwrap_or(0);
+            let points: Vec<_> = records.iter().map(|record| record.points).collect();
+
+            lines.push(fill(&fill(&STRINGS.rounds_correct, correct), records.len()));
+            lines.push(format!(
+                "{} {}",
+                STRINGS.summary_points.as_str().bold(),
+                sparkline(&points).cyan()
+            ));
+            lines.push(STRINGS.summary_languages.as_str().bold().to_string());
+            lines.extend(languages.into_iter().map(|(language, accuracy)| {
+                let padding = " ".repeat(width - language.width());
+                format!("  {language}{padding}  {accuracy}")
+            }));
+        }
+
+        lines.push(fill(
+            &STRINGS.requests,
+            format_number(API_CALLS.load(Ordering::Relaxed))
+                .cyan()
+                .bold(),
+        ));
+
+        if points > high_score {
+            if high_score > 0 {
+                lines.push(fill(
+                    &STRINGS.beat_high_score,
+                    format_number(high_score).magenta().bold(),
+                ));
+
+                if !ARGS.no_share {
+                    lines.push(String::new());
+                    lines.push(fill(
+                        &STRINGS.share,
+                        "https://github.com/Lioness100/guess-that-lang/discussions/6"
+                            .cyan()
+                            .bold(),
+                    ));
+                }
+            } else {
+                lines.push(STRINGS.new_high_score.as_str().magenta().bold().to_string());
             }
+        }
 
-            let mut stdout = self.stdout.lock();
+        if let Some(challenge) = Challenge::from_args(&ARGS) {
+            lines.push(fill(
+                &STRINGS.challenge_code,
+                challenge.encode().cyan().bold(),
+            ));
+        }
 
-            // Move to the row index of the dotted code and replace it with the
-            // real code.
-            queue!(stdout, SavePosition, MoveTo(9, idx as u16 + 5), Print(line))?;
+        let mut writer = self.lock()?;
+        for line in lines {
+            writeln!(writer, "{line}")?;
+        }
 
-            // `available_points` should not be decreased on the first line.
-            if idx != 0 {
-                let mut available_points = available_points.lock().map_err(|_| "could not lock")?;
-                *available_points -= 10.0;
-
-                // https://stackoverflow.com/a/7947812/13721990
-                let new_color = Color::RGB(
-                    255.0_f32.min(255.0 * 2.0 * (1.0 - (*available_points / 100.0))) as u8,
-                    255.0_f32.min(2.0 * 255.0 * (*available_points / 100.0)) as u8,
-                    0,
-                );
+        writer.flush().map_err(Into::into)
+    }
 
+    /// Print the option to replay the last round below the other options.
+    pub fn print_replay_option(&self) -> Result<()> {
+        let mut stdout = self.lock()?;
+        execute!(
+            stdout,
+            Print(format!("\r\n{}", Self::format_option("l", &STRINGS.replay)))
+        )
+        .map_err(Into::into)
+    }
+
+    /// Reveal all lines of code at once and decrease `available_points` every
+    /// second, from 100 to 10 over `study_time` seconds. This is used instead
+    /// of [`Terminal::start_showing_code`] when `--study-time` is provided.
+    pub fn start_study_timer(
+        &self,
+        code_lines: &[CodeLine],
+        available_points: &Mutex<f32>,
+        is_revealed: &AtomicBool,
+        receiver: Receiver<RevealSignal>,
+        study_time: u16,
+    ) -> Result<()> {
+        let study_time = study_time.max(1);
+        let layout = self.layout()?;
+
+        {
+            let mut stdout = self.lock()?;
+            queue!(stdout, SavePosition)?;
+
+            for (idx, line) in code_lines.iter().enumerate() {
                 queue!(
                     stdout,
-                    MoveTo(27, 3),
-                    Print(format!(
-                        "{} ",
-                        new_color.paint(available_points.to_string())
-                    ))
+                    layout.code_position(idx, 0),
+                    Print(&line.highlighted)
                 )?;
             }
 
+            queue!(
+                stdout,
+                layout.points_position(2, &STRINGS.available_points),
+                Print(format!(
+                    "{} {}",
+                    Self::paint_points(Difficulty::current().starting_points()),
+                    Self::format_countdown(study_time, study_time)
+                ))
+            )?;
+
             execute!(stdout, RestorePosition)?;
+            is_revealed.store(true, Ordering::Relaxed);
+        }
+
+        // Consume receiver.
+        let receiver = receiver;
+
+        for elapsed in 1..=study_time {
+            if self.wait_for_step(&receiver, Duration::from_secs(1), layout)? {
+                break;
+            }
+
+            let mut stdout = self.lock()?;
+            // The points are lowered rather than set, so that the points paid
+            // for hints stay paid.
+            let mut available_points = available_points.lock()?;
+            *available_points = (*available_points - Self::study_points(elapsed - 1, study_time)
+                + Self::study_points(elapsed, study_time))
+            .max(0.0);
+
+            execute!(
+                stdout,
+                SavePosition,
+                layout.points_position(2, &STRINGS.available_points),
+                Print(format!(
+                    "{} {}",
+                    Self::paint_points(*available_points),
+                    Self::format_countdown(study_time - elapsed, study_time)
+                )),
+                RestorePosition
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Format the accuracy shown after the available points. They're repainted
+    /// in place with a trailing space, followed by the countdown with
+    /// `--study-time`, so this is far enough from them to not be overwritten.
+    #[must_use]
+    pub fn format_accuracy(accuracy: Accuracy) -> String {
+        let countdown_width = ARGS.study_time.map_or(0, |study_time| {
+            Self::format_countdown(0, study_time).chars().count()
+        });
+
+        format!(
+            "{}    {} {accuracy}",
+            " ".repeat(countdown_width),
+            STRINGS.accuracy.as_str().bold()
+        )
+    }
+
+    /// Format the countdown shown after the available points with
+    /// `--study-time`, padded to the width of the first one so that it
+    /// overwrites the previous one. It's blank once there's no time left.
+    #[must_use]
+    pub fn format_countdown(remaining: u16, study_time: u16) -> String {
+        let width = fill(&STRINGS.study_countdown, study_time).chars().count() + 1;
+        let countdown = if remaining == 0 {
+            String::new()
+        } else {
+            fill(&STRINGS.study_countdown, remaining)
+        };
+
+        format!("{countdown:width$}")
+    }
+
+    /// Get the available points `elapsed` seconds into `--study-time`, before
+    /// paying for hints.
+    #[must_use]
+    pub fn study_points(elapsed: u16, study_time: u16) -> f32 {
+        (Difficulty::current().starting_points()
+            * (1.0 - 0.9 * f32::from(elapsed) / f32::from(study_time)))
+        .round()
+    }
+
+    /// Color the available points on the gradient of the [`Palette`], e.g.
+    /// from green (100) to red (0).
+    #[must_use]
+    pub fn paint_points(points: f32) -> String {
+        Palette::current()
+            .color(points)
+            .paint(points.to_string())
+            .to_string()
+    }
+
+    /// Utility function to format the wager shown next to the total points.
+    #[must_use]
+    pub fn format_wager(wager: u32) -> String {
+        format!(
+            "  {} {} ",
+            STRINGS.wager.as_str().bold(),
+            format_number(wager).yellow()
+        )
+    }
+
+    /// Utility function to format the row below the points that hints are
+    /// shown in, which is only there with `--hints`.
+    #[must_use]
+    pub fn format_hint_row() -> String {
+        if !ARGS.hints {
+            return String::new();
+        }
+
+        format!(
+            "\r\n{}{} {} {}",
+            " ".repeat(Layout::GUTTER_WIDTH),
+            "│".white().dim(),
+            STRINGS.hint.as_str().bold(),
+            fill(&STRINGS.hint_cost, HINT_COST).dark_grey()
+        )
+    }
+
+    /// Replace the wager shown next to the total points.
+    pub fn print_wager(&self, total_points: u32, wager: u32) -> Result<()> {
+        let mut stdout = self.lock()?;
+
+        // The total points start after "│ Total Points: ".
+        let layout = self.layout()?;
+        let column = Layout::points_column(&STRINGS.total_points)
+            + format_number(total_points).chars().count() as u16;
+
+        execute!(
+            stdout,
+            SavePosition,
+            MoveTo(column, layout.points_row(1)),
+            Print(Self::format_wager(wager)),
+            Print("    "),
+            RestorePosition
+        )
+        .map_err(Into::into)
+    }
+
+    /// Read an answer typed on the first option row with `--difficulty
+    /// expert`, returning the index of the option it matches, or [`None`] if
+    /// the user quit with Esc or Ctrl+C. Answers that don't match an option
+    /// are cleared with a note, and with `--no-blind-guess`, they can't be
+    /// given until the first line is revealed. Reaching `deadline` counts as
+    /// quitting.
+    pub fn read_typed_answer(
+        &self,
+        options: &[&str],
+        is_revealed: &AtomicBool,
+        deadline: Option<Instant>,
+    ) -> Result<Option<usize>> {
+        let layout = self.layout()?;
+        let mut answer = String::new();
+
+        loop {
+            if !Terminal::poll_until(deadline)? {
+                return Ok(None);
+            }
+
+            let (code, modifiers) = match event::read()? {
+                Event::Key(KeyEvent {
+                    code, modifiers, ..
+                }) => (code, modifiers),
+                _ => continue,
+            };
+
+            let mut note = String::new();
+            match code {
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Char('c') if modifiers == KeyModifiers::CONTROL => return Ok(None),
+                KeyCode::Enter if ARGS.no_blind_guess && !is_revealed.load(Ordering::Relaxed) => {
+                    continue;
+                }
+                KeyCode::Enter => match Terminal::match_typed_answer(options, &answer) {
+                    Some(idx) => return Ok(Some(idx)),
+                    None if answer.trim().is_empty() => continue,
+                    None => {
+                        note = fill(&STRINGS.unknown_answer, answer.trim());
+                        answer.clear();
+                    }
+                },
+                KeyCode::Backspace => {
+                    answer.pop();
+                }
+                KeyCode::Char(char) if !modifiers.contains(KeyModifiers::CONTROL) => {
+                    answer.push(char);
+                }
+                _ => continue,
+            }
+
+            let mut stdout = self.lock()?;
+            queue!(stdout, SavePosition)?;
+            Self::queue_move_to_option(&mut stdout, layout, 0)?;
+            queue!(
+                stdout,
+                Clear(ClearType::UntilNewLine),
+                Print(Self::format_option(">", &answer))
+            )?;
+            Self::queue_move_to_option(&mut stdout, layout, 1)?;
+            execute!(
+                stdout,
+                Clear(ClearType::UntilNewLine),
+                Print(format!("{}{}", " ".repeat(5), note.dark_grey())),
+                RestorePosition
+            )?;
+        }
+    }
+
+    /// Format the points awarded for a correct answer with the available
+    /// points at `available_points`, like `+ 99 (x1.1), + 20 speed bonus`.
+    #[must_use]
+    pub fn format_awards(available_points: f32, stakes: &Stakes) -> String {
+        let multiplier = stakes.multiplier();
+        let awarded = scoring::apply_multiplier(available_points, multiplier);
+
+        let mut awards = vec![if multiplier > 1.0 {
+            format!("+ {awarded} ({})", scoring::format_multiplier(multiplier))
+        } else {
+            format!("+ {awarded}")
+        }];
+        if stakes.speed_bonus > 0 {
+            awards.push(format!(
+                "+ {}",
+                fill(&STRINGS.speed_bonus, stakes.speed_bonus)
+            ));
+        }
+        if stakes.wager > 0 {
+            awards.push(format!("+ {}", fill(&STRINGS.wagered, stakes.wager)));
         }
 
-        Ok(())
+        awards.join(", ")
     }
 
-    /// Responds to input from the user (1 | 2 | 3 | 4).
+    /// Responds to input from the user (the number of an option).
     #[allow(clippy::unnecessary_to_owned)]
     pub fn process_input(
         &self,
@@ -385,88 +2119,175 @@ impl Terminal {
         correct_language: &str,
         available_points: &Mutex<f32>,
         total_points: &mut u32,
+        stakes: &Stakes,
     ) -> Result<ControlFlow<()>> {
+        let Stakes { wager, .. } = *stakes;
+
         // Locking the stdout will let any work that's being done in
         // [`Terminal::start_showing_code`] to finish before we continue.
-        let mut stdout = self.stdout.lock();
+        let layout = self.layout()?;
+        let mut stdout = self.lock()?;
 
         let correct_option_idx = options
             .iter()
             .position(|&option| option == correct_language)
-            .ok_or("correct language not found")?;
+            .ok_or_else(|| GuessError::Other(String::from("correct language not found")))?;
 
         let was_correct = (correct_option_idx + 1) as u32 == num;
-        let available_points = available_points.lock().map_err(|_| "could not lock")?;
+        let available_points = available_points.lock()?;
+        // The answer to a bonus round isn't a language, so it isn't tracked.
+        if !stakes.is_bonus {
+            stats::save_answer(
+                correct_language,
+                was_correct,
+                stakes.points_won(was_correct, *available_points),
+            );
+        }
 
         let correct_option_name_text = if was_correct {
-            format!("{correct_language} (+ {available_points})")
+            format!(
+                "{correct_language} ({})",
+                Self::format_awards(*available_points, stakes)
+            )
+        } else {
+            format!("{correct_language} ({})", STRINGS.correct)
+        };
+
+        // With expert, the typed answer is marked on the first row, and the
+        // correct answer is shown below it if they're different.
+        let is_typed = Difficulty::current().is_typed();
+        let (correct_key, correct_row) = match (is_typed, was_correct) {
+            (false, _) => ((correct_option_idx + 1).to_string(), correct_option_idx),
+            (true, true) => (String::from(">"), 0),
+            (true, false) => (String::from("="), 1),
+        };
+        let (incorrect_key, incorrect_row) = if is_typed {
+            (String::from(">"), 0)
         } else {
-            format!("{correct_language} (Correct)")
+            (num.to_string(), num as usize - 1)
         };
 
+        let palette = Palette::current();
         let correct_option_text = Self::format_option(
-            &(correct_option_idx + 1).to_string(),
-            &correct_option_name_text.green().bold().to_string(),
+            &correct_key,
+            &palette
+                .color(100.0)
+                .bold()
+                .paint(format!(
+                    "{}{correct_option_name_text}",
+                    palette.symbol(true)
+                ))
+                .to_string(),
         );
 
+        queue!(stdout, SavePosition)?;
+        Self::queue_move_to_option(&mut stdout, layout, correct_row)?;
         queue!(
             stdout,
-            SavePosition,
-            MoveUp((4 - correct_option_idx) as u16),
-            MoveToColumn(0),
+            Clear(ClearType::UntilNewLine),
             Print(correct_option_text),
             RestorePosition
         )?;
 
         if was_correct {
-            *total_points += *available_points as u32;
+            *total_points += stakes.points_won(true, *available_points) as u32;
             stdout.flush()?;
 
             Ok(ControlFlow::Continue(()))
         } else {
+            *total_points = total_points.saturating_sub(wager);
+
+            let incorrect_option_name_text = if wager > 0 {
+                format!(
+                    "{} ({}, - {})",
+                    options[num as usize - 1],
+                    STRINGS.incorrect,
+                    fill(&STRINGS.wagered, wager)
+                )
+            } else {
+                format!("{} ({})", options[num as usize - 1], STRINGS.incorrect)
+            };
+
             let incorrect_option_text = Self::format_option(
-                &num.to_string(),
-                &Color::RGB(255, 0, 51)
+                &incorrect_key,
+                &palette
+                    .color(0.0)
                     .bold()
-                    .paint(format!("{} (Incorrect)", options[num as usize - 1]))
+                    .paint(format!(
+                        "{}{incorrect_option_name_text}",
+                        palette.symbol(false)
+                    ))
                     .to_string(),
             );
 
-            execute!(
+            queue!(stdout, SavePosition)?;
+            Self::queue_move_to_option(&mut stdout, layout, incorrect_row)?;
+            queue!(
                 stdout,
-                SavePosition,
-                MoveUp((5 - num) as u16),
-                MoveToColumn(0),
+                Clear(ClearType::UntilNewLine),
                 Print(incorrect_option_text),
                 RestorePosition
             )?;
 
+            if ARGS.teach {
+                if let Some(note) = Game::get_note(correct_language, options[num as usize - 1]) {
+                    queue!(stdout, Print(format!("\r\n\r\n{}", note.italic())))?;
+                }
+            }
+
+            stdout.flush()?;
             Ok(ControlFlow::Break(()))
         }
     }
 
-    /// Utility function to wait for a relevant char to be pressed.
-    pub fn read_input_char() -> Result<char> {
-        // Consume all ready-to-be-collected events to ensure that only future
-        // are collected.
-        while event::poll(Duration::from_millis(1))? {
-            event::read()?;
+    /// Print the cues from [`crate::game::LANGUAGE_CUES`] that gave away the
+    /// correct language, if there were any.
+    pub fn print_cues(&self, language: &str, cues: &[&str]) -> Result<()> {
+        if cues.is_empty() {
+            return Ok(());
         }
 
-        loop {
-            if let Event::Key(KeyEvent {
-                code: KeyCode::Char(char @ ('1' | '2' | '3' | '4' | 'q' | 'c')),
-                modifiers,
-                ..
-            }) = event::read()?
-            {
-                if char == 'c' && modifiers != KeyModifiers::CONTROL {
-                    continue;
-                }
+        let mut stdout = self.lock()?;
+        execute!(
+            stdout,
+            Print(format!("\r\n\r\n{}", Self::format_cues(language, cues)))
+        )
+        .map_err(Into::into)
+    }
 
-                return Ok(char);
-            }
+    /// Print the other options that the code had cues for after a wrong
+    /// answer, from [`Game::get_feedback`].
+    pub fn print_feedback(&self, feedback: &[(&str, Vec<&str>)]) -> Result<()> {
+        if feedback.is_empty() {
+            return Ok(());
         }
+
+        let reasons: String = feedback
+            .iter()
+            .map(|(language, cues)| format!("\r\n  {}", Self::format_cues(language, cues)))
+            .collect();
+
+        let mut stdout = self.lock()?;
+        execute!(
+            stdout,
+            Print(format!(
+                "\r\n\r\n{}{reasons}",
+                STRINGS.reasonable.as_str().bold()
+            ))
+        )
+        .map_err(Into::into)
+    }
+
+    /// Format cues as "`cue`, `cue` → language".
+    #[must_use]
+    pub fn format_cues(language: &str, cues: &[&str]) -> String {
+        let cues = cues
+            .iter()
+            .map(|cue| format!("`{cue}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!("{cues} → {language}").italic().to_string()
     }
 
     /// Utility function to format an option.
@@ -479,13 +2300,129 @@ impl Terminal {
             name = name
         )
     }
+}
 
-    /// Get terminal width.
-    pub fn width() -> Result<usize> {
-        terminal::size()
-            .map(|(width, _)| width as usize)
-            .map_err(Into::into)
+/// Get the longest start of `text` that's at most `width` columns wide.
+#[must_use]
+pub fn truncate_to_width(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    let end = text
+        .char_indices()
+        .find_map(|(idx, char)| {
+            used += char.width().unwrap_or(0);
+            (used > width).then_some(idx)
+        })
+        .unwrap_or(text.len());
+
+    &text[..end]
+}
+
+/// Ask `question` and read answers from `reader` until `parse` accepts one,
+/// printing why the others weren't. An empty answer is taken as `default`, if
+/// there is one. This is used outside of raw mode, e.g. by
+/// [`crate::setup::run`].
+pub fn ask<T>(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+    question: &str,
+    default: Option<&str>,
+    parse: impl Fn(&str) -> result::Result<T, String>,
+) -> Result<T> {
+    loop {
+        match default {
+            Some(default) => write!(writer, "{question} [{}]: ", default.dark_grey())?,
+            None => write!(writer, "{question}: ")?,
+        }
+        writer.flush()?;
+
+        let mut answer = String::new();
+        if reader.read_line(&mut answer)? == 0 {
+            return Err(GuessError::Other(String::from("No answer was given")));
+        }
+
+        let answer = match (answer.trim(), default) {
+            ("", Some(default)) => default,
+            (answer, _) => answer,
+        };
+
+        match parse(answer) {
+            Ok(value) => return Ok(value),
+            Err(reason) => writeln!(writer, "{}", reason.red())?,
+        }
+    }
+}
+
+/// Format the `lives` left out of `max` as hearts and a ✗ for each one that
+/// was lost, like `♥♥` and `✗`, which are shown one after the other in
+/// different colors.
+#[must_use]
+pub fn format_lives(lives: u32, max: u32) -> (String, String) {
+    (
+        "♥".repeat(lives as usize),
+        "✗".repeat(max.saturating_sub(lives) as usize),
+    )
+}
+
+/// Format `values` as a row of bars as high as each value is relative to the
+/// highest one, like `▁▄█`. Values below zero are as low as zero.
+#[must_use]
+pub fn sparkline(values: &[i64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|&value| BARS[(value.clamp(0, max) * 7 / max) as usize])
+        .collect()
+}
+
+/// Get the number of seconds in `duration`, rounded up.
+#[must_use]
+pub fn ceil_seconds(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+/// Format the time left in blitz mode like `1:05`, rounding up so that `0:00`
+/// is only shown once the time is up.
+#[must_use]
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = ceil_seconds(duration);
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Format a number with thousands separators, using the separator of the
+/// user's locale where it's known.
+#[must_use]
+pub fn format_number(number: u32) -> String {
+    let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .iter()
+        .find_map(|key| env::var(key).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_default();
+
+    let separator = match locale.get(..2) {
+        Some("de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da") => '.',
+        Some("fr" | "ru" | "pl" | "cs" | "sv" | "fi" | "nb" | "uk") => ' ',
+        _ => ',',
+    };
+
+    group_digits(number, separator)
+}
+
+/// Insert `separator` between every group of three digits.
+#[must_use]
+pub fn group_digits(number: u32, separator: char) -> String {
+    let digits = number.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx != 0 && (digits.len() - idx) % 3 == 0 {
+            grouped.push(separator);
+        }
+
+        grouped.push(digit);
     }
+
+    grouped
 }
 
 #[cfg(target_os = "macos")]
@@ -497,11 +2434,51 @@ fn macos_dark_mode_active() -> bool {
         .map_or(true, |output| output.stdout == b"Dark\n")
 }
 
+// Like on macOS, any failure (e.g. `gsettings` not being installed) falls back
+// to dark mode.
+#[cfg(target_os = "linux")]
+fn linux_dark_mode_active() -> bool {
+    let gsettings = |key: &str| {
+        std::process::Command::new("gsettings")
+            .args(["get", "org.gnome.desktop.interface", key])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).to_lowercase())
+    };
+
+    match gsettings("color-scheme") {
+        Some(scheme) if scheme.contains("dark") => true,
+        Some(scheme) if scheme.contains("light") => false,
+        _ => gsettings("gtk-theme").map_or(true, |theme| theme.contains("dark")),
+    }
+}
+
+#[cfg(windows)]
+fn windows_dark_mode_active() -> bool {
+    let mut reg_cmd = std::process::Command::new("reg");
+    reg_cmd.args([
+        "query",
+        r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+        "/v",
+        "AppsUseLightTheme",
+    ]);
+    reg_cmd.output().map_or(true, |output| {
+        // The value is printed as e.g. `AppsUseLightTheme    REG_DWORD    0x0`.
+        !String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .any(|word| word == "0x1")
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::{RoundKind, LANGUAGES};
     use indoc::indoc;
     use lazy_static::lazy_static;
+    use proptest::prelude::*;
+    use rand::SeedableRng;
 
     lazy_static! {
         static ref TERMINAL: Terminal = Terminal::new().unwrap();
@@ -509,8 +2486,45 @@ mod tests {
 
     const WIDTH: &usize = &500;
 
-    fn parse_code(code: &str) -> Option<Vec<(String, String)>> {
-        TERMINAL.parse_code(code, TERMINAL.get_highlighter("Rust"), WIDTH)
+    fn parse_code(code: &str) -> Option<Vec<CodeLine>> {
+        TERMINAL.parse_code(
+            code,
+            TERMINAL.get_highlighter("Rust"),
+            WIDTH,
+            usize::MAX,
+            true,
+        )
+    }
+
+    #[test]
+    fn syntax_fallbacks() {
+        let syntax_name = |language: &str, content_type: Option<&str>, code: &str| {
+            let data = CodeData {
+                code: code.to_string(),
+                language: language.to_string(),
+                content_type: content_type.map(String::from),
+                source: None,
+            };
+
+            TERMINAL.find_code_syntax(&data).name.clone()
+        };
+
+        assert_eq!(syntax_name("Rust", Some("text/x-python"), ""), "Rust");
+        assert_eq!(syntax_name("", Some("text/x-python"), ""), "Python");
+        assert_eq!(syntax_name("", Some("TEXT/CSS"), ""), "CSS");
+        assert_eq!(syntax_name("", Some("text/plain"), "#!/bin/bash"), "Shell");
+        assert_eq!(syntax_name("", None, "let x = 5;"), "Plain Text");
+        assert_eq!(syntax_name("Assembly", None, ""), "Assembly (x86_64)");
+    }
+
+    #[test]
+    fn every_language_has_syntax() {
+        for language in LANGUAGES {
+            assert!(
+                TERMINAL.find_language_syntax(language).is_some(),
+                "{language} has no syntax"
+            );
+        }
     }
 
     #[test]
@@ -519,11 +2533,27 @@ mod tests {
         let parsed = parse_code(&code).unwrap();
 
         assert_eq!(
-            parsed[0].0,
+            parsed[0].raw,
             "_".repeat(WIDTH - 3 - "   1   | ".len()) + "..."
         );
     }
 
+    #[test]
+    fn cut_off_wide_unicode() {
+        let code = format!("x{}", "日本語".repeat(300));
+        let parsed = parse_code(&code).unwrap();
+        let raw = &parsed[0].raw;
+
+        // Each character is 2 columns wide, so the one after the "x" leaves a
+        // column of space left over.
+        assert!(raw.ends_with("..."));
+        assert_eq!(raw.width(), WIDTH - "   1   | ".len() - 1);
+        assert_eq!(
+            parsed[0].tokens.last().unwrap().0,
+            WIDTH - "   1   | ".len() - 4
+        );
+    }
+
     #[test]
     fn remove_comments() {
         let code = indoc! {"
@@ -537,6 +2567,49 @@ mod tests {
 
         let parsed = parse_code(code).unwrap();
         assert_eq!(parsed.len(), 1);
+
+        // Comments are recognized by their scope, so every line of a block
+        // comment is removed, and with any theme.
+        let code = "/*\nStill a comment\n*/\nlet x = 5;\n";
+        let themes: ThemeSet = dumps::from_binary(include_bytes!("../assets/dumps/themes.dump"));
+        let mut terminal = Terminal::with_writer(Vec::new()).unwrap();
+
+        for theme in themes.themes.into_values() {
+            terminal.theme = theme;
+
+            let parsed = terminal
+                .parse_code(
+                    code,
+                    terminal.get_highlighter("Rust"),
+                    WIDTH,
+                    usize::MAX,
+                    true,
+                )
+                .unwrap();
+            assert_eq!(parsed.len(), 1);
+            assert_eq!(parsed[0].raw, "let x = 5;\n");
+        }
+    }
+
+    #[test]
+    fn keep_comments() {
+        let code = indoc! {"
+            // Should be kept
+            /* Should be kept */
+        "};
+
+        assert!(parse_code(code).is_none());
+
+        let parsed = TERMINAL
+            .parse_code(
+                code,
+                TERMINAL.get_highlighter("Rust"),
+                WIDTH,
+                usize::MAX,
+                false,
+            )
+            .unwrap();
+        assert_eq!(parsed.len(), 2);
     }
 
     #[test]
@@ -579,6 +2652,424 @@ mod tests {
         assert_eq!(parsed.len(), code.lines().count() - 2);
     }
 
+    proptest! {
+        #[test]
+        fn parse_code_invariants(code in "[ -~\n日本語éß]{0,2000}", width in 0_usize..200, max_rows in 1_usize..30) {
+            let lines = TERMINAL.parse_code(&code, TERMINAL.get_highlighter("Rust"), &width, max_rows, true);
+
+            if let Some(lines) = lines {
+                if width >= Layout::CODE_COLUMN + ARGS.truncation_marker.width() {
+                    prop_assert!(lines.iter().all(|line| line.raw.trim_end().width() + Layout::CODE_COLUMN <= width));
+                }
+
+                prop_assert!(lines.iter().filter(|line| !line.is_blank()).count() <= usize::from(ARGS.max_lines));
+                prop_assert!(lines.len() <= max_rows);
+                prop_assert!(!lines[0].is_blank());
+                prop_assert!(!lines[lines.len() - 1].is_blank());
+                prop_assert!(!lines.windows(2).any(|pair| pair[0].is_blank() && pair[1].is_blank()));
+            }
+        }
+    }
+
+    #[test]
+    fn distinct_tokens() {
+        let count = |code: &str| {
+            let syntax = TERMINAL.syntaxes.find_syntax_by_name("Rust").unwrap();
+            TERMINAL.count_distinct_tokens(&parse_code(code).unwrap(), syntax)
+        };
+
+        let empty = indoc! {"
+            {
+                foo
+            }
+        "};
+        assert_eq!(count(empty), 0);
+
+        let keywords = indoc! {"
+            fn main() {
+                let x = if y { 1 } else { 2 };
+                let z = x + 1;
+            }
+        "};
+        assert!(count(keywords) >= 5);
+    }
+
+    #[test]
+    fn token_positions() {
+        let code = "fn main() {\n    let x = \"a b\";\n}\n";
+        let lines = parse_code(code).unwrap();
+
+        for line in lines {
+            // Placing every token at its column should rebuild the line.
+            let mut rebuilt = vec![' '; line.raw.chars().count()];
+            for (column, token) in &line.tokens {
+                let mut in_escape = false;
+                let text = token.chars().filter(|&char| {
+                    // Skip the color escape sequences.
+                    in_escape |= char == '\x1b';
+                    let is_text = !in_escape;
+                    in_escape &= char != 'm';
+                    is_text
+                });
+
+                for (offset, char) in text.enumerate() {
+                    rebuilt[column + offset] = char;
+                }
+            }
+
+            let rebuilt: String = rebuilt.into_iter().collect();
+            assert_eq!(rebuilt.trim_end(), line.raw.trim_end());
+        }
+    }
+
+    #[test]
+    fn wrap_long_lines() {
+        let code = "let numbers = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];\n";
+        let rows = TERMINAL
+            .highlight_line(code, &mut TERMINAL.get_highlighter("Rust"), true, 20)
+            .unwrap();
+
+        // Nothing is lost, and every row but the first continues the line.
+        assert_eq!(
+            rows.iter().map(|row| row.raw.as_str()).collect::<String>(),
+            code
+        );
+        assert!(rows.iter().all(|row| row.raw.trim_end().width() <= 20));
+        assert!(!rows[0].is_continuation && rows[1..].iter().all(|row| row.is_continuation));
+        assert_eq!(rows.len(), 4);
+
+        let mut lines = parse_code("let x = 5;\n").unwrap();
+        lines.extend(rows);
+
+        let numbers = Terminal::<Vec<u8>>::line_numbers(&lines);
+        assert_eq!(numbers, [Some(1), Some(2), None, None, None]);
+
+        // The rows of the wrapped line are revealed in one step.
+        let steps = Terminal::<Vec<u8>>::get_reveal_steps(
+            &lines,
+            RevealMode::BottomUp,
+            false,
+            &mut StdRng::seed_from_u64(0),
+        );
+        let step_rows: Vec<Vec<_>> = steps
+            .iter()
+            .map(|step| step.iter().map(|(row, _, _)| *row).collect())
+            .collect();
+        assert_eq!(step_rows, [vec![1, 2, 3, 4], vec![0]]);
+    }
+
+    #[test]
+    fn wait_for_steps() {
+        let terminal = Terminal::with_writer(Vec::new()).unwrap();
+        let layout = Layout::default();
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        assert!(!terminal
+            .wait_for_step(&receiver, Duration::from_millis(1), layout)
+            .unwrap());
+
+        sender.send(RevealSignal::Answered).unwrap();
+        assert!(terminal
+            .wait_for_step(&receiver, Duration::from_secs(10), layout)
+            .unwrap());
+
+        // Nothing more is revealed once input stops being read.
+        drop(sender);
+        assert!(terminal
+            .wait_for_step(&receiver, Duration::from_secs(10), layout)
+            .unwrap());
+    }
+
+    #[test]
+    fn capture_bottom_up_reveal() {
+        let terminal = Terminal::with_writer(Vec::new()).unwrap();
+        let code = parse_code("let x = 5;\n\nlet y = 6;\nlet z = 7;\n").unwrap();
+
+        let layout = Layout::new(code.len(), 4, false, false);
+        let line_numbers = Terminal::<Vec<u8>>::line_numbers(&code);
+
+        for step in Terminal::<Vec<u8>>::get_reveal_steps(
+            &code,
+            RevealMode::BottomUp,
+            false,
+            &mut StdRng::seed_from_u64(0),
+        ) {
+            let mut writer = terminal.lock().unwrap();
+            Terminal::queue_reveal_step(&mut *writer, layout, &line_numbers, &step).unwrap();
+        }
+
+        // The code starts at row 5 and column 9, and cursor positions are
+        // 1-based.
+        let output = String::from_utf8(terminal.writer.into_inner().unwrap()).unwrap();
+        let positions: Vec<_> = ["\x1b[9;10H", "\x1b[8;10H", "\x1b[6;10H"]
+            .iter()
+            .map(|position| output.find(position).unwrap())
+            .collect();
+
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(!output.contains("\x1b[7;10H"));
+    }
+
+    #[test]
+    fn palette_colors() {
+        assert_eq!(Palette::Normal.color(100.0), Color::RGB(0, 255, 0));
+        assert_eq!(Palette::Normal.color(0.0), Color::RGB(255, 0, 0));
+        assert_eq!(Palette::Colorblind.color(100.0), Color::RGB(0, 114, 178));
+        assert_eq!(Palette::Colorblind.color(0.0), Color::RGB(230, 159, 0));
+        assert_eq!(
+            Palette::Colorblind.color(150.0),
+            Palette::Colorblind.color(100.0)
+        );
+        assert_eq!(Palette::Mono.color(50.0), Color::White);
+
+        assert_eq!(Palette::Normal.symbol(true), "");
+        assert_eq!(Palette::Mono.symbol(false), "✗ ");
+        assert_eq!("colorblind".parse(), Ok(Palette::Colorblind));
+        assert!("rainbow".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn typed_answers() {
+        let options = ["C", "C#", "C++", "Rust"];
+
+        assert_eq!(Terminal::match_typed_answer(&options, "rust"), Some(3));
+        assert_eq!(Terminal::match_typed_answer(&options, "  c# "), Some(1));
+        assert_eq!(Terminal::match_typed_answer(&options, "c"), Some(0));
+        assert_eq!(Terminal::match_typed_answer(&options, "rus"), None);
+        assert_eq!(Terminal::match_typed_answer(&options, ""), None);
+    }
+
+    #[test]
+    fn layout_coordinates() {
+        let layout = Layout::new(3, 4, false, false);
+        assert_eq!(
+            (layout.table_row, layout.code_row, layout.option_row),
+            (0, 5, 12)
+        );
+        assert_eq!(layout.prompt_row(), 10);
+        assert_eq!(layout.points_row(2), 3);
+
+        let layout = Layout::new(3, 4, true, false);
+        assert_eq!(
+            (layout.table_row, layout.code_row, layout.option_row),
+            (8, 13, 2)
+        );
+        assert_eq!(layout.points_row(2), 11);
+
+        let layout = Layout::new(3, 6, true, false);
+        assert_eq!(
+            (layout.table_row, layout.code_row, layout.option_row),
+            (10, 15, 2)
+        );
+
+        let layout = Layout::new(3, 4, false, true);
+        assert_eq!(
+            (layout.table_row, layout.code_row, layout.option_row),
+            (0, 6, 13)
+        );
+        assert_eq!(layout.points_row(3), 4);
+
+        assert_eq!(Layout::CODE_COLUMN, "   1   │ ".chars().count());
+        assert_eq!(Layout::points_column("Total Points:"), 23);
+
+        // The quit option is on the row before last, leaving the last one for
+        // the cursor.
+        let rows = Layout::max_code_rows(24, 4);
+        assert_eq!(Layout::new(rows, 4, false, false).option_row + 4, 22);
+        assert_eq!(Layout::max_code_rows(5, 6), 1);
+    }
+
+    #[test]
+    fn study_countdown() {
+        assert_eq!(Terminal::<Vec<u8>>::format_countdown(10, 10), "(10s left) ");
+        assert_eq!(Terminal::<Vec<u8>>::format_countdown(9, 10), "(9s left)  ");
+        assert_eq!(Terminal::<Vec<u8>>::format_countdown(0, 10), " ".repeat(11));
+    }
+
+    #[test]
+    fn capture_round_info() {
+        let terminal = Terminal::with_writer(Vec::new()).unwrap();
+        let code = parse_code("let x = 5;\n").unwrap();
+
+        terminal
+            .print_round_info(
+                &["Rust", "C", "Go", "Java"],
+                &code,
+                WIDTH,
+                0,
+                Some(Accuracy::default()),
+                None,
+            )
+            .unwrap();
+
+        let layout = terminal.layout().unwrap();
+        assert_eq!(layout, Layout::new(code.len(), 4, false, false));
+
+        let output = String::from_utf8(terminal.writer.into_inner().unwrap()).unwrap();
+        assert!(output.contains("··· · · ··"));
+        assert!(output.contains("Java"));
+        assert!(output.contains(&format!("{}\x1b[0m -", STRINGS.accuracy)));
+
+        // The round info is printed from the top of the screen.
+        let rows: Vec<_> = output.split("\r\n").collect();
+        let option_row = usize::from(layout.option_row);
+        assert!(rows[option_row].contains("Rust"));
+        assert!(rows[option_row + 3].contains("Java"));
+        assert!(rows[option_row + 4].contains(&STRINGS.quit));
+
+        // A terminal narrower than the gutter doesn't have room for the
+        // separators, but still works.
+        let terminal = Terminal::with_writer(Vec::new()).unwrap();
+        assert!(terminal
+            .print_round_info(&["Rust", "C", "Go", "Java"], &code, &3, 0, None, None)
+            .is_ok());
+    }
+
+    #[test]
+    fn review_shows_all_code() {
+        let code: String = (1..=30)
+            .map(|num| format!("let x{num} = {num};\n"))
+            .collect();
+        let round = Round {
+            kind: RoundKind::Guess,
+            language: String::from("Rust"),
+            code: Vec::new(),
+            options: Vec::new(),
+            full_code: TERMINAL.highlight_code(&code, TERMINAL.get_highlighter("Rust")),
+            source: Some(String::from("https://gist.github.com/abc")),
+            unavailable: Vec::new(),
+        };
+
+        let review = Terminal::<Vec<u8>>::format_review(&round);
+        assert!(review.contains("x30"));
+        assert!(review.contains("  30   "));
+        assert!(review.contains("https://gist.github.com/abc"));
+        assert!(review.contains(&STRINGS.review_continue));
+    }
+
+    #[test]
+    fn line_costs_fit_gutter() {
+        assert_eq!(
+            Terminal::<Vec<u8>>::format_gutter(Some(12), false),
+            "  12   "
+        );
+        assert_eq!(Terminal::<Vec<u8>>::format_gutter(None, true), "       ");
+
+        for line_number in [1, 42, 100] {
+            let gutter = Terminal::<Vec<u8>>::format_gutter(Some(line_number), true);
+            let visible = gutter.replace("\x1b[38;5;8m", "").replace("\x1b[39m", "");
+
+            assert_eq!(visible, format!("{line_number:>3} -10"));
+        }
+    }
+
+    #[test]
+    fn click_options() {
+        let clicked = |option_count| -> Vec<_> {
+            let layout = Layout::new(3, option_count, true, false);
+            (0..10)
+                .map(|row| Terminal::clicked_option(row, layout))
+                .collect()
+        };
+
+        assert_eq!(
+            clicked(4),
+            [
+                None,
+                None,
+                Some('1'),
+                Some('2'),
+                Some('3'),
+                Some('4'),
+                Some('q'),
+                None,
+                None,
+                None
+            ]
+        );
+        assert_eq!(clicked(6)[6..], [Some('5'), Some('6'), Some('q'), None]);
+    }
+
+    #[test]
+    fn digit_grouping() {
+        assert_eq!(group_digits(0, ','), "0");
+        assert_eq!(group_digits(999, ','), "999");
+        assert_eq!(group_digits(1000, ','), "1,000");
+        assert_eq!(group_digits(1_234_567, '.'), "1.234.567");
+    }
+
+    #[test]
+    fn blitz_clock() {
+        assert_eq!(format_duration(Duration::from_secs(120)), "2:00");
+        assert_eq!(format_duration(Duration::from_millis(64_200)), "1:05");
+        assert_eq!(format_duration(Duration::from_nanos(1)), "0:01");
+        assert_eq!(format_duration(Duration::ZERO), "0:00");
+    }
+
+    #[test]
+    fn ask_until_valid() {
+        let mut input = io::Cursor::new("x\n\n42\n");
+        let mut output = Vec::new();
+        let parse = |answer: &str| answer.parse::<u32>().map_err(|err| err.to_string());
+
+        assert_eq!(
+            ask(&mut input, &mut output, "Wait", None, parse).unwrap(),
+            42
+        );
+        assert_eq!(
+            String::from_utf8(output).unwrap().matches("Wait: ").count(),
+            3
+        );
+
+        let mut input = io::Cursor::new("\n");
+        assert_eq!(
+            ask(&mut input, &mut Vec::new(), "Wait", Some("7"), parse).unwrap(),
+            7
+        );
+        assert!(ask(&mut input, &mut Vec::new(), "Wait", Some("7"), parse).is_err());
+    }
+
+    #[test]
+    fn lives_marks() {
+        let joined = |(hearts, lost): (String, String)| hearts + &lost;
+        assert_eq!(joined(format_lives(3, 3)), "♥♥♥");
+        assert_eq!(joined(format_lives(1, 3)), "♥✗✗");
+        assert_eq!(joined(format_lives(0, 2)), "✗✗");
+    }
+
+    #[test]
+    fn points_sparkline() {
+        assert_eq!(sparkline(&[100, 50, 0, -20]), "█▄▁▁");
+        assert_eq!(sparkline(&[0, 0]), "▁▁");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn capture_summary() {
+        let terminal = Terminal::with_writer(Vec::new()).unwrap();
+        let record = |language: &str, was_correct, points| RoundRecord {
+            language: language.to_string(),
+            options: Vec::new(),
+            answer: String::new(),
+            was_correct,
+            points,
+        };
+
+        let records = [
+            record("Rust", true, 90),
+            record("Go", true, 45),
+            record("Rust", false, 0),
+        ];
+        terminal.print_summary(135, 100, &records).unwrap();
+
+        let output = String::from_utf8(terminal.writer.into_inner().unwrap()).unwrap();
+        assert!(output.contains(&fill(&fill(&STRINGS.rounds_correct, 2), 3)));
+        assert!(output.contains("█▄▁"));
+        assert!(output.contains("Rust  50% (1/2)"));
+        assert!(output.contains("Go    100% (1/1)"));
+        assert!(output.contains(&STRINGS.beat_high_score.replace(" {}!", "")));
+    }
+
     #[test]
     fn trim_newlines() {
         let code = indoc! {"
@@ -592,4 +3083,79 @@ mod tests {
         let parsed = parse_code(code).unwrap();
         assert_eq!(parsed.len(), 1);
     }
+
+    #[test]
+    fn find_themes() {
+        let themes: ThemeSet = dumps::from_binary(include_bytes!("../assets/dumps/themes.dump"));
+
+        for style in [ThemeStyle::Dark, ThemeStyle::Light] {
+            assert!(Terminal::find_theme(&themes, style.into()).is_ok());
+        }
+
+        assert!(Terminal::find_theme(&themes, "monokai extended").is_ok());
+
+        let error = Terminal::find_theme(&themes, "Nonexistent")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Monokai Extended Light"));
+    }
+
+    #[test]
+    fn every_theme_and_language() {
+        // A comment and a line of code for each language in
+        // [`crate::game::LANGUAGES`].
+        let samples = [
+            ("Assembly", "; add\nmov eax, 1\n"),
+            ("Shell", "# greet\necho \"hi\"\n"),
+            ("C", "// main\nint main(void) { return 0; }\n"),
+            ("C#", "// main\nConsole.WriteLine(\"hi\");\n"),
+            ("C++", "// main\nstd::cout << \"hi\";\n"),
+            ("CSS", "/* body */\nbody { color: red; }\n"),
+            ("Dart", "// main\nvoid main() => print('hi');\n"),
+            ("Dockerfile", "# base\nFROM rust:latest\n"),
+            ("Go", "// main\nfunc main() { fmt.Println(\"hi\") }\n"),
+            ("Groovy", "// greet\nprintln 'hi'\n"),
+            ("HTML", "<!-- body -->\n<div class=\"a\">hi</div>\n"),
+            ("Java", "// greet\nSystem.out.println(\"hi\");\n"),
+            ("JavaScript", "// greet\nconsole.log('hi');\n"),
+            ("Kotlin", "// greet\nfun main() = println(\"hi\")\n"),
+            ("Lua", "-- greet\nlocal x = 1\n"),
+            ("MATLAB", "% greet\nx = zeros(3);\n"),
+            ("PHP", "<?php\n// greet\necho 'hi';\n"),
+            ("PowerShell", "# greet\nWrite-Host 'hi'\n"),
+            ("Python", "# greet\nprint('hi')\n"),
+            ("R", "# greet\nx <- c(1, 2)\n"),
+            ("Ruby", "# greet\nputs 'hi'\n"),
+            ("Rust", "// greet\nfn main() { println!(\"hi\"); }\n"),
+            ("SQL", "-- users\nSELECT * FROM users;\n"),
+            ("Swift", "// greet\nlet x = 1\n"),
+            ("TypeScript", "// greet\nconst x: number = 1;\n"),
+        ];
+
+        assert!(LANGUAGES
+            .iter()
+            .all(|language| samples.iter().any(|(name, _)| name == language)));
+
+        let themes: ThemeSet = dumps::from_binary(include_bytes!("../assets/dumps/themes.dump"));
+        let mut terminal = Terminal::with_writer(Vec::new()).unwrap();
+
+        for (name, theme) in themes.themes {
+            terminal.theme = theme;
+
+            for (language, code) in samples {
+                let lines = terminal.parse_code(
+                    code,
+                    terminal.get_highlighter(language),
+                    WIDTH,
+                    usize::MAX,
+                    true,
+                );
+
+                assert!(
+                    lines.map_or(false, |lines| !lines.is_empty()),
+                    "every line of {language} was removed with {name}"
+                );
+            }
+        }
+    }
 }